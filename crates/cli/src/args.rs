@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+use tailwindcss_oxide::GlobEntry;
+
+pub const USAGE: &str = "\
+Usage: tailwindcss-oxide <command> [options]
+
+Commands:
+  scan               Print all candidates
+  files              Print all files that are scanned
+  globs              Print all globs that are used to find files
+  positions <file>   Print all candidates in a file, with their positions
+
+Options:
+  --base <path>              Detect sources in the base path, can be repeated
+  --source <base:pattern>    Scan files matching the glob pattern, can be repeated
+  --format <format>          Output format: plain (default), json or ndjson
+  -h, --help                 Print this help message";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Scan,
+    Files,
+    Globs,
+    Positions(PathBuf),
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// One value per line
+    #[default]
+    Plain,
+
+    /// A single JSON array
+    Json,
+
+    /// One JSON value per line
+    Ndjson,
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub command: Command,
+    pub bases: Vec<PathBuf>,
+    pub sources: Vec<GlobEntry>,
+    pub format: Format,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut command = None;
+        let mut bases = vec![];
+        let mut sources = vec![];
+        let mut format = Format::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Self::help()),
+                "--base" => bases.push(PathBuf::from(value("--base")?)),
+                "--source" => sources.push(parse_source(&value("--source")?)?),
+                "--format" => {
+                    format = match value("--format")?.as_str() {
+                        "plain" => Format::Plain,
+                        "json" => Format::Json,
+                        "ndjson" => Format::Ndjson,
+                        other => return Err(format!("Unknown format: {}", other)),
+                    }
+                }
+                arg if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+                _ if command.is_some() => return Err(format!("Unexpected argument: {}", arg)),
+                "scan" => command = Some(Command::Scan),
+                "files" => command = Some(Command::Files),
+                "globs" => command = Some(Command::Globs),
+                "positions" => {
+                    command = Some(Command::Positions(PathBuf::from(value("positions")?)))
+                }
+                _ => return Err(format!("Unknown command: {}", arg)),
+            }
+        }
+
+        let Some(command) = command else {
+            return Err("Missing command".into());
+        };
+
+        Ok(Self {
+            command,
+            bases,
+            sources,
+            format,
+        })
+    }
+
+    fn help() -> Self {
+        Self {
+            command: Command::Help,
+            bases: vec![],
+            sources: vec![],
+            format: Format::default(),
+        }
+    }
+}
+
+/// Parse a `base:pattern` source. The last `:` is used, so that Windows paths like `C:\project`
+/// can be used as the base.
+fn parse_source(source: &str) -> Result<GlobEntry, String> {
+    match source.rsplit_once(':') {
+        Some((base, pattern)) if !base.is_empty() && !pattern.is_empty() => Ok(GlobEntry {
+            base: base.into(),
+            pattern: pattern.into(),
+        }),
+        _ => Err(format!(
+            "Invalid source, expected `base:pattern`: {}",
+            source
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|x| x.to_string()))
+    }
+
+    #[test]
+    fn it_parses_commands_and_options() {
+        let args = parse(&[
+            "scan",
+            "--base",
+            "/project",
+            "--base",
+            "/packages/ui",
+            "--source",
+            "/project/src:**/*.html",
+            "--source",
+            "C:\\project:*.js",
+            "--format",
+            "ndjson",
+        ])
+        .unwrap();
+
+        assert_eq!(args.command, Command::Scan);
+        assert_eq!(
+            args.bases,
+            vec![PathBuf::from("/project"), PathBuf::from("/packages/ui")]
+        );
+        assert_eq!(args.format, Format::Ndjson);
+        assert_eq!(
+            args.sources
+                .iter()
+                .map(|x| (x.base.as_str(), x.pattern.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("/project/src", "**/*.html"), ("C:\\project", "*.js")]
+        );
+
+        let args = parse(&["--format", "json", "positions", "index.html"]).unwrap();
+        assert_eq!(args.command, Command::Positions("index.html".into()));
+        assert_eq!(args.format, Format::Json);
+
+        assert_eq!(parse(&["globs", "--help"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn it_rejects_invalid_arguments() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["build"]).is_err());
+        assert!(parse(&["scan", "files"]).is_err());
+        assert!(parse(&["scan", "--watch"]).is_err());
+        assert!(parse(&["scan", "--base"]).is_err());
+        assert!(parse(&["scan", "--format", "xml"]).is_err());
+        assert!(parse(&["scan", "--source", "**/*.html"]).is_err());
+        assert!(parse(&["positions"]).is_err());
+    }
+}
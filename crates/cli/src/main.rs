@@ -0,0 +1,194 @@
+use args::{Args, Command, Format, USAGE};
+use serde_json::{json, Value};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tailwindcss_oxide::scanner::detect_sources::DetectSources;
+use tailwindcss_oxide::utf16::{self, Position};
+use tailwindcss_oxide::{ChangedContent, Scanner};
+
+mod args;
+
+/// A single line of output, in plain and JSON form
+struct Item {
+    plain: String,
+    json: Value,
+}
+
+impl From<String> for Item {
+    fn from(value: String) -> Self {
+        Self {
+            json: Value::String(value.clone()),
+            plain: value,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.command == Command::Help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let mut scanner = Scanner::new(
+        detect_sources(&args.bases),
+        Some(args.sources.clone()).filter(|sources| !sources.is_empty()),
+    );
+
+    let items = match run(&mut scanner, &args) {
+        Ok(items) => items,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // A closed pipe, e.g.: when piping into `head`, is not an error
+    _ = print(&items, args.format);
+
+    for diagnostic in scanner.diagnostics() {
+        eprintln!("warning: {}", diagnostic);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Detect sources in all bases, if any
+fn detect_sources(bases: &[PathBuf]) -> Option<DetectSources> {
+    let (base, rest) = bases.split_first()?;
+
+    Some(
+        rest.iter()
+            .fold(DetectSources::new(base.clone()), |acc, base| {
+                acc.with_base(base.clone())
+            }),
+    )
+}
+
+fn run(scanner: &mut Scanner, args: &Args) -> Result<Vec<Item>, String> {
+    let items = match &args.command {
+        Command::Help => vec![],
+        Command::Scan => scanner.scan().into_iter().map(Into::into).collect(),
+        Command::Files => scanner.get_files().into_iter().map(Into::into).collect(),
+        Command::Globs => scanner
+            .get_globs()
+            .into_iter()
+            .map(|glob| Item {
+                plain: Path::new(&glob.base)
+                    .join(&glob.pattern)
+                    .display()
+                    .to_string(),
+                json: json!({ "base": glob.base, "pattern": glob.pattern }),
+            })
+            .collect(),
+        Command::Positions(file) => positions(scanner, file)?,
+    };
+
+    Ok(items)
+}
+
+fn positions(scanner: &mut Scanner, file: &Path) -> Result<Vec<Item>, String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|err| format!("Failed to read {}: {}", file.display(), err))?;
+
+    let candidates = scanner
+        .get_candidates_with_positions(ChangedContent {
+            file: None,
+            content: Some(content.clone()),
+            extension: file
+                .extension()
+                .and_then(|x| x.to_str())
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .map_err(|err| err.to_string())?;
+
+    // The same positions and shape as `getCandidatesWithPositions` of the Node API
+    Ok(utf16::candidate_ranges(&content, candidates)
+        .into_iter()
+        .map(|(candidate, start, end)| Item {
+            plain: format!(
+                "{}:{}-{}:{}\t{}",
+                start.line, start.column, end.line, end.column, candidate
+            ),
+            json: json!({
+                "candidate": candidate,
+                "position": start.utf16,
+                "range": {
+                    "start": position_json(start),
+                    "end": position_json(end),
+                },
+            }),
+        })
+        .collect())
+}
+
+fn position_json(position: Position) -> Value {
+    json!({
+        "byte": position.byte,
+        "utf16": position.utf16,
+        "line": position.line,
+        "column": position.column,
+    })
+}
+
+fn print(items: &[Item], format: Format) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+
+    match format {
+        Format::Plain => {
+            for item in items {
+                writeln!(out, "{}", item.plain)?;
+            }
+        }
+        Format::Json => {
+            let values: Vec<Value> = items.iter().map(|item| item.json.clone()).collect();
+            writeln!(out, "{}", Value::Array(values))?;
+        }
+        Format::Ndjson => {
+            for item in items {
+                writeln!(out, "{}", item.json)?;
+            }
+        }
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_prints_positions_in_utf16_code_units() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("index.html");
+        fs::write(
+            &file,
+            "<p class=\"🔥 flex\">\r\n  <b class=\"é underline\"></b>",
+        )
+        .unwrap();
+
+        let items = positions(&mut Scanner::new(None, None), &file).unwrap();
+        let plain: Vec<&str> = items.iter().map(|item| item.plain.as_str()).collect();
+
+        assert!(plain.contains(&"1:14-1:18\tflex"));
+        assert!(plain.contains(&"2:15-2:24\tunderline"));
+
+        let flex = items.iter().find(|item| item.json["candidate"] == "flex");
+        assert_eq!(
+            flex.unwrap().json["range"]["start"],
+            json!({ "byte": 15, "utf16": 13, "line": 1, "column": 14 })
+        );
+    }
+}
@@ -1,9 +1,15 @@
-use utf16::IndexConverter;
+use napi::bindgen_prelude::AsyncTask;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::JsFunction;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tailwindcss_oxide::utf16;
+use tasks::{DetectSourcesTask, ScanFilesTask, ScanTask};
 
 #[macro_use]
 extern crate napi_derive;
 
-mod utf16;
+mod tasks;
 
 #[derive(Debug, Clone)]
 #[napi(object)]
@@ -23,6 +29,34 @@ pub struct ChangedContent {
 pub struct DetectSources {
   /// Base path to start scanning from
   pub base: String,
+
+  /// Additional base paths to scan, e.g.: other packages in a monorepo
+  pub bases: Option<Vec<String>>,
+
+  /// Extensions, files and directories to ignore in addition to the built-in ones
+  pub ignore: Option<IgnoreList>,
+
+  /// Built-in ignored extensions, files and directories that should be scanned after all
+  pub unignore: Option<IgnoreList>,
+
+  /// Skip files that look binary based on their content, regardless of their extension
+  pub sniff_content: Option<bool>,
+
+  /// Scan files without an extension, as long as their content doesn't look binary
+  pub extensionless_files: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct IgnoreList {
+  /// Extensions without the leading `.`, e.g.: `json`
+  pub extensions: Option<Vec<String>>,
+
+  /// File names, e.g.: `package-lock.json`
+  pub files: Option<Vec<String>>,
+
+  /// Directory names, e.g.: `vendor`
+  pub dirs: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +74,7 @@ impl From<ChangedContent> for tailwindcss_oxide::ChangedContent {
     Self {
       file: changed_content.file.map(Into::into),
       content: changed_content.content,
+      extension: changed_content.extension,
     }
   }
 }
@@ -62,9 +97,38 @@ impl From<tailwindcss_oxide::GlobEntry> for GlobEntry {
   }
 }
 
+impl From<IgnoreList> for tailwindcss_oxide::scanner::allowed_paths::IgnoreList {
+  fn from(list: IgnoreList) -> Self {
+    Self {
+      extensions: list.extensions.unwrap_or_default(),
+      files: list.files.unwrap_or_default(),
+      dirs: list.dirs.unwrap_or_default(),
+    }
+  }
+}
+
 impl From<DetectSources> for tailwindcss_oxide::scanner::detect_sources::DetectSources {
   fn from(detect_sources: DetectSources) -> Self {
-    Self::new(detect_sources.base.into())
+    let mut rules = tailwindcss_oxide::scanner::allowed_paths::IgnoreRules::default()
+      .with_content_sniffing(detect_sources.sniff_content.unwrap_or(false))
+      .with_extensionless_files(detect_sources.extensionless_files.unwrap_or(false));
+
+    if let Some(ignore) = detect_sources.ignore {
+      rules = rules.ignore(ignore.into());
+    }
+
+    if let Some(unignore) = detect_sources.unignore {
+      rules = rules.unignore(unignore.into());
+    }
+
+    detect_sources
+      .bases
+      .unwrap_or_default()
+      .into_iter()
+      .fold(Self::new(detect_sources.base.into()), |acc, base| {
+        acc.with_base(base.into())
+      })
+      .with_ignore_rules(rules)
   }
 }
 
@@ -78,12 +142,124 @@ pub struct ScannerOptions {
 
   /// Glob sources
   pub sources: Option<Vec<GlobEntry>>,
+
+  /// Persist scan results in this file, so that unchanged files are not read again in a later run.
+  /// The file is written by `flushCache`, and when the scanner is garbage collected.
+  pub cache: Option<String>,
+
+  /// How to decide whether a file changed since it was last scanned: `mtime` (default),
+  /// `size-mtime` or `content-hash`
+  pub change_detection: Option<String>,
+
+  /// Skip files that are larger than this many bytes
+  pub max_file_size: Option<u32>,
+
+  /// Skip files where the average line is longer than this many bytes, e.g.: minified bundles
+  pub max_average_line_length: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Diagnostic {
+  /// `read-file`, `invalid-glob`, `missing-base`, `watch`, `file-too-large` or `minified-file`
+  pub kind: String,
+
+  /// Human readable description of the problem
+  pub message: String,
+
+  /// The file or directory the problem is about
+  pub path: Option<String>,
+}
+
+impl From<tailwindcss_oxide::ScanError> for Diagnostic {
+  fn from(error: tailwindcss_oxide::ScanError) -> Self {
+    use tailwindcss_oxide::ScanError;
+
+    Self {
+      kind: match error {
+        ScanError::ReadFile { .. } => "read-file".into(),
+        ScanError::InvalidGlob { .. } => "invalid-glob".into(),
+        ScanError::MissingBase { .. } => "missing-base".into(),
+        ScanError::Watch { .. } => "watch".into(),
+        ScanError::FileTooLarge { .. } => "file-too-large".into(),
+        ScanError::MinifiedFile { .. } => "minified-file".into(),
+      },
+      message: error.to_string(),
+      path: error.path().map(|path| path.to_string_lossy().into()),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanOptions {
+  /// Base path to detect sources in
+  pub base: Option<String>,
+
+  /// Glob sources
+  pub sources: Option<Vec<GlobEntry>>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct ScanResult {
+  pub candidates: Vec<String>,
+  pub files: Vec<String>,
+  pub globs: Vec<GlobEntry>,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Detect sources, resolve globs and extract all candidates in a single call. Use a `Scanner` when
+/// files have to be scanned again later.
+#[napi]
+pub fn scan(opts: ScanOptions) -> ScanResult {
+  let result = tailwindcss_oxide::scan(tailwindcss_oxide::ScanOptions {
+    base: opts.base,
+    sources: opts
+      .sources
+      .unwrap_or_default()
+      .into_iter()
+      .map(Into::into)
+      .collect(),
+  });
+
+  ScanResult {
+    candidates: result.candidates,
+    files: result.files,
+    globs: result.globs.into_iter().map(Into::into).collect(),
+    diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Sources {
+  /// All files that will be scanned
+  pub files: Vec<String>,
+
+  /// All globs that are used to find files
+  pub globs: Vec<GlobEntry>,
 }
 
 #[derive(Debug, Clone)]
 #[napi]
 pub struct Scanner {
-  scanner: tailwindcss_oxide::Scanner,
+  scanner: Arc<Mutex<tailwindcss_oxide::Scanner>>,
+}
+
+#[derive(Debug)]
+#[napi]
+pub struct Watcher {
+  watcher: Option<tailwindcss_oxide::scanner::watcher::Watcher>,
+}
+
+#[napi]
+impl Watcher {
+  /// Stop watching for changes
+  #[napi]
+  pub fn close(&mut self) {
+    self.watcher.take();
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -94,42 +270,336 @@ pub struct CandidateWithPosition {
 
   /// The position of the candidate inside the content file
   pub position: i64,
+
+  /// The full range of the candidate inside the content file
+  pub range: CandidateRange,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateRange {
+  /// Where the candidate starts
+  pub start: Position,
+
+  /// Where the candidate ends (exclusive)
+  pub end: Position,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Position {
+  /// UTF-8 byte offset
+  pub byte: i64,
+
+  /// UTF-16 code unit offset
+  pub utf16: i64,
+
+  /// 1-based line number
+  pub line: i64,
+
+  /// 1-based column, in UTF-16 code units
+  pub column: i64,
+}
+
+impl From<utf16::Position> for Position {
+  fn from(position: utf16::Position) -> Self {
+    Self {
+      byte: position.byte as i64,
+      utf16: position.utf16,
+      line: position.line,
+      column: position.column,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateChanges {
+  /// Candidates that were not used before
+  pub added: Vec<String>,
+
+  /// Candidates that are no longer used by any file
+  pub removed: Vec<String>,
+}
+
+impl From<tailwindcss_oxide::CandidateChanges> for CandidateChanges {
+  fn from(changes: tailwindcss_oxide::CandidateChanges) -> Self {
+    Self {
+      added: changes.added,
+      removed: changes.removed,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct FileChanges {
+  /// Files that are new sources
+  pub added: Vec<String>,
+
+  /// Files that are no longer sources
+  pub removed: Vec<String>,
+
+  /// Candidates that are no longer used, because they were only used by removed files
+  pub candidates: CandidateChanges,
+}
+
+impl From<tailwindcss_oxide::FileChanges> for FileChanges {
+  fn from(changes: tailwindcss_oxide::FileChanges) -> Self {
+    Self {
+      added: changes
+        .added
+        .into_iter()
+        .map(|file| file.to_string_lossy().into())
+        .collect(),
+      removed: changes
+        .removed
+        .into_iter()
+        .map(|file| file.to_string_lossy().into())
+        .collect(),
+      candidates: changes.candidates.into(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct CandidateValue {
+  /// Either `named` or `arbitrary`
+  pub kind: String,
+
+  /// The value, e.g.: `red-500` or `#0088cc`
+  pub value: String,
+
+  /// The type hint of an arbitrary value, e.g.: `color` in `bg-[color:var(--brand)]`
+  pub data_type: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+#[napi(object)]
+pub struct Candidate {
+  /// The candidate as it was written
+  pub raw: String,
+
+  /// Either `utility` or `arbitrary-property`
+  pub kind: String,
+
+  /// Variants in the order they are written
+  pub variants: Vec<String>,
+
+  /// The utility root, or the property of an arbitrary property
+  pub root: String,
+
+  /// The value of the utility
+  pub value: Option<CandidateValue>,
+
+  /// The modifier after the `/`
+  pub modifier: Option<CandidateValue>,
+
+  /// The end of the value and the modifier read as a fraction, e.g.: `1/2` for `w-1/2` or
+  /// `-translate-x-1/2`
+  pub fraction: Option<String>,
+
+  /// Whether the utility is negated
+  pub negative: bool,
+
+  /// Whether the utility is marked as important
+  pub important: bool,
+}
+
+impl From<tailwindcss_oxide::candidate::CandidateValue> for CandidateValue {
+  fn from(value: tailwindcss_oxide::candidate::CandidateValue) -> Self {
+    use tailwindcss_oxide::candidate::CandidateValue::*;
+
+    match value {
+      Named(value) => Self {
+        kind: "named".into(),
+        value,
+        data_type: None,
+      },
+      Arbitrary { value, data_type } => Self {
+        kind: "arbitrary".into(),
+        value,
+        data_type,
+      },
+    }
+  }
+}
+
+impl From<tailwindcss_oxide::candidate::Candidate> for Candidate {
+  fn from(candidate: tailwindcss_oxide::candidate::Candidate) -> Self {
+    use tailwindcss_oxide::candidate::CandidateKind;
+
+    Self {
+      raw: candidate.raw,
+      kind: match candidate.kind {
+        CandidateKind::Utility => "utility".into(),
+        CandidateKind::ArbitraryProperty => "arbitrary-property".into(),
+      },
+      variants: candidate.variants,
+      root: candidate.root,
+      value: candidate.value.map(Into::into),
+      modifier: candidate.modifier.map(Into::into),
+      fraction: candidate.fraction,
+      negative: candidate.negative,
+      important: candidate.important,
+    }
+  }
+}
+
+/// Split candidates into their structural parts. Candidates that can't be parsed are `null`.
+#[napi]
+pub fn parse_candidates(candidates: Vec<String>) -> Vec<Option<Candidate>> {
+  candidates
+    .iter()
+    .map(|candidate| tailwindcss_oxide::candidate::Candidate::parse(candidate).map(Into::into))
+    .collect()
 }
 
 #[napi]
 impl Scanner {
   #[napi(constructor)]
-  pub fn new(opts: ScannerOptions) -> Self {
-    Self {
-      scanner: tailwindcss_oxide::Scanner::new(
-        opts.detect_sources.map(Into::into),
-        opts
-          .sources
-          .map(|x| x.into_iter().map(Into::into).collect()),
-      ),
+  pub fn new(opts: ScannerOptions) -> napi::Result<Self> {
+    let mut scanner = tailwindcss_oxide::Scanner::new(
+      opts.detect_sources.map(Into::into),
+      opts
+        .sources
+        .map(|x| x.into_iter().map(Into::into).collect()),
+    );
+
+    if let Some(cache) = opts.cache {
+      scanner = scanner.with_cache_file(cache.into());
+    }
+
+    if let Some(change_detection) = opts.change_detection {
+      scanner = scanner.with_change_detection(match change_detection.as_str() {
+        "mtime" => tailwindcss_oxide::ChangeDetection::Mtime,
+        "size-mtime" => tailwindcss_oxide::ChangeDetection::SizeAndMtime,
+        "content-hash" => tailwindcss_oxide::ChangeDetection::ContentHash,
+        other => {
+          return Err(napi::Error::from_reason(format!(
+            "Invalid changeDetection `{}`, expected `mtime`, `size-mtime` or `content-hash`",
+            other
+          )))
+        }
+      });
     }
+
+    scanner = scanner.with_file_limits(tailwindcss_oxide::FileLimits {
+      max_file_size: opts.max_file_size.map(Into::into),
+      max_average_line_length: opts.max_average_line_length.map(|x| x as usize),
+    });
+
+    Ok(Self {
+      scanner: Arc::new(Mutex::new(scanner)),
+    })
+  }
+
+  /// Lock the scanner. Sync calls wait for a running async task or watcher scan to finish, so they
+  /// always see a consistent scanner.
+  fn scanner(&self) -> MutexGuard<'_, tailwindcss_oxide::Scanner> {
+    self.scanner.lock().unwrap_or_else(|err| err.into_inner())
   }
 
   #[napi]
   pub fn scan(&mut self) -> Vec<String> {
-    self.scanner.scan()
+    self.scanner().scan()
+  }
+
+  /// Same as `scan`, but the file system is scanned on a worker thread
+  #[napi(ts_return_type = "Promise<string[]>")]
+  pub fn scan_async(&self) -> AsyncTask<ScanTask> {
+    AsyncTask::new(ScanTask {
+      scanner: self.scanner.clone(),
+    })
+  }
+
+  #[napi]
+  pub fn scan_parsed(&mut self) -> Vec<Candidate> {
+    self
+      .scanner()
+      .scan_parsed()
+      .into_iter()
+      .map(Into::into)
+      .collect()
   }
 
   #[napi]
   pub fn scan_files(&mut self, input: Vec<ChangedContent>) -> Vec<String> {
     self
-      .scanner
+      .scanner()
       .scan_content(input.into_iter().map(Into::into).collect())
   }
 
+  /// Same as `scanFiles`, but the files are read and parsed on a worker thread
+  #[napi(ts_return_type = "Promise<string[]>")]
+  pub fn scan_files_async(&self, input: Vec<ChangedContent>) -> AsyncTask<ScanFilesTask> {
+    AsyncTask::new(ScanFilesTask {
+      scanner: self.scanner.clone(),
+      input,
+    })
+  }
+
+  #[napi]
+  pub fn scan_files_with_changes(&mut self, input: Vec<ChangedContent>) -> CandidateChanges {
+    self
+      .scanner()
+      .scan_content_with_changes(input.into_iter().map(Into::into).collect())
+      .into()
+  }
+
+  #[napi]
+  pub fn remove_files(&mut self, files: Vec<String>) -> CandidateChanges {
+    self
+      .scanner()
+      .remove_files(files.into_iter().map(Into::into).collect())
+      .into()
+  }
+
+  /// Detect sources and resolve globs again, to pick up files that were created or deleted. New
+  /// files are scanned by the next `scan`.
+  #[napi]
+  pub fn refresh(&mut self) -> FileChanges {
+    self.scanner().refresh().into()
+  }
+
+  /// Replace the glob sources. Files that are still sources keep their candidates, only new files
+  /// are scanned by the next `scan`.
+  #[napi]
+  pub fn set_sources(&mut self, sources: Option<Vec<GlobEntry>>) -> FileChanges {
+    self
+      .scanner()
+      .set_sources(sources.map(|x| x.into_iter().map(Into::into).collect()))
+      .into()
+  }
+
+  /// Same as `setSources`, but replaces the automatic source detection.
+  #[napi]
+  pub fn set_detect_sources(&mut self, detect_sources: Option<DetectSources>) -> FileChanges {
+    self
+      .scanner()
+      .set_detect_sources(detect_sources.map(Into::into))
+      .into()
+  }
+
   #[napi]
   pub fn get_candidates_with_positions(
     &mut self,
     input: ChangedContent,
-  ) -> Vec<CandidateWithPosition> {
-    let content = input.content.unwrap_or_else(|| {
-      std::fs::read_to_string(&input.file.unwrap()).expect("Failed to read file")
-    });
+  ) -> napi::Result<Vec<CandidateWithPosition>> {
+    let content = match (input.content, input.file) {
+      (Some(content), _) => content,
+      (None, Some(file)) => std::fs::read_to_string(&file).map_err(|err| {
+        napi::Error::from_reason(
+          tailwindcss_oxide::ScanError::ReadFile {
+            file: file.into(),
+            message: err.to_string(),
+          }
+          .to_string(),
+        )
+      })?,
+      (None, None) => String::new(),
+    };
 
     let input = ChangedContent {
       file: None,
@@ -137,28 +607,113 @@ impl Scanner {
       extension: input.extension,
     };
 
-    let mut utf16_idx = IndexConverter::new(&content[..]);
+    let candidates = self
+      .scanner()
+      .get_candidates_with_positions(input.into())
+      .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+
+    Ok(
+      utf16::candidate_ranges(&content, candidates)
+        .into_iter()
+        .map(|(candidate, start, end)| CandidateWithPosition {
+          candidate,
+          position: start.utf16,
+          range: CandidateRange {
+            start: start.into(),
+            end: end.into(),
+          },
+        })
+        .collect(),
+    )
+  }
+
+  /// Write the scan results to the `cache` file, when files were scanned since it was last written
+  #[napi]
+  pub fn flush_cache(&mut self) {
+    self.scanner().flush_cache()
+  }
 
+  /// Problems that were found while resolving the sources and reading files
+  #[napi(getter)]
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
     self
-      .scanner
-      .get_candidates_with_positions(input.into())
+      .scanner()
+      .diagnostics()
       .into_iter()
-      .map(|(candidate, position)| CandidateWithPosition {
-        candidate,
-        position: utf16_idx.get(position),
-      })
+      .map(Into::into)
+      .collect()
+  }
+
+  /// Whether the file is a source, e.g.: to filter file system events. Paths should be absolute.
+  #[napi]
+  pub fn matches(&mut self, path: String) -> bool {
+    self.scanner().matches(path.as_ref())
+  }
+
+  /// Only keep the paths that are sources, see `matches`.
+  #[napi]
+  pub fn filter(&mut self, paths: Vec<String>) -> Vec<String> {
+    let mut scanner = self.scanner();
+
+    paths
+      .into_iter()
+      .filter(|path| scanner.matches(path.as_ref()))
       .collect()
   }
 
+  #[napi]
+  pub fn files_for_candidate(&self, candidate: String) -> Vec<String> {
+    self.scanner().get_files_for_candidate(&candidate)
+  }
+
+  #[napi]
+  pub fn candidates_for_file(&self, file: String) -> Vec<String> {
+    self.scanner().get_candidates_for_file(file.as_ref())
+  }
+
+  /// Watch all sources for changes, and call the callback with the candidates that were added or
+  /// removed. Changes within `debounce` milliseconds (default: 50) of each other are reported at
+  /// once.
+  #[napi]
+  pub fn watch(
+    &self,
+    #[napi(ts_arg_type = "(changes: CandidateChanges) => void")] callback: JsFunction,
+    debounce: Option<u32>,
+  ) -> napi::Result<Watcher> {
+    let callback: ThreadsafeFunction<CandidateChanges, ErrorStrategy::Fatal> =
+      callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+
+    let watcher = tailwindcss_oxide::Scanner::watch(
+      self.scanner.clone(),
+      Duration::from_millis(debounce.unwrap_or(50).into()),
+      move |changes| {
+        callback.call(changes.into(), ThreadsafeFunctionCallMode::NonBlocking);
+      },
+    )
+    .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+
+    Ok(Watcher {
+      watcher: Some(watcher),
+    })
+  }
+
+  /// Resolve the `files` and `globs` on a worker thread
+  #[napi(ts_return_type = "Promise<Sources>")]
+  pub fn detect_sources_async(&self) -> AsyncTask<DetectSourcesTask> {
+    AsyncTask::new(DetectSourcesTask {
+      scanner: self.scanner.clone(),
+    })
+  }
+
   #[napi(getter)]
   pub fn files(&mut self) -> Vec<String> {
-    self.scanner.get_files()
+    self.scanner().get_files()
   }
 
   #[napi(getter)]
   pub fn globs(&mut self) -> Vec<GlobEntry> {
     self
-      .scanner
+      .scanner()
       .get_globs()
       .into_iter()
       .map(Into::into)
@@ -0,0 +1,113 @@
+use crate::{ChangedContent, GlobEntry, Sources};
+use napi::{Env, Task};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+type SharedScanner = Arc<Mutex<tailwindcss_oxide::Scanner>>;
+
+/// Lock the scanner from a worker thread. A panic in another task doesn't leave the scanner in an
+/// invalid state, so we can keep using it.
+fn lock(scanner: &SharedScanner) -> MutexGuard<'_, tailwindcss_oxide::Scanner> {
+  scanner.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+pub struct ScanTask {
+  pub(crate) scanner: SharedScanner,
+}
+
+impl Task for ScanTask {
+  type Output = Vec<String>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(lock(&self.scanner).scan())
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct ScanFilesTask {
+  pub(crate) scanner: SharedScanner,
+  pub(crate) input: Vec<ChangedContent>,
+}
+
+impl Task for ScanFilesTask {
+  type Output = Vec<String>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let input = std::mem::take(&mut self.input);
+
+    Ok(lock(&self.scanner).scan_content(input.into_iter().map(Into::into).collect()))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct DetectSourcesTask {
+  pub(crate) scanner: SharedScanner,
+}
+
+impl Task for DetectSourcesTask {
+  type Output = Sources;
+  type JsValue = Sources;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let mut scanner = lock(&self.scanner);
+
+    Ok(Sources {
+      files: scanner.get_files(),
+      globs: scanner
+        .get_globs()
+        .into_iter()
+        .map(GlobEntry::from)
+        .collect(),
+    })
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Scanner, ScannerOptions};
+  use std::fs;
+  use std::time::Duration;
+
+  #[test]
+  fn it_waits_for_running_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("index.html"), "flex").unwrap();
+
+    let mut scanner = Scanner::new(ScannerOptions {
+      detect_sources: None,
+      sources: Some(vec![GlobEntry {
+        base: dir.path().display().to_string(),
+        pattern: "*.html".into(),
+      }]),
+      cache: None,
+      change_detection: None,
+      max_file_size: None,
+      max_average_line_length: None,
+    })
+    .unwrap();
+
+    // A running task holds the scanner
+    let shared = scanner.scanner.clone();
+    let running = lock(&shared);
+
+    let files = std::thread::spawn(move || scanner.files());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!files.is_finished());
+
+    // Sync calls continue once the task is done
+    drop(running);
+    assert_eq!(files.join().unwrap().len(), 1);
+  }
+}
@@ -0,0 +1,239 @@
+//! Times source detection on a generated project, and compares it with the multiple walks that
+//! source detection used before it was done in a single walk.
+//!
+//! `cargo bench -p tailwindcss-oxide --bench detect_sources`
+
+use fxhash::FxHashSet;
+use ignore::WalkBuilder;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tailwindcss_oxide::scanner::allowed_paths::{IgnoreRules, TAILWIND_IGNORE_FILE};
+use tailwindcss_oxide::scanner::detect_sources::DetectSources;
+use walkdir::WalkDir;
+
+/// Create a monorepo-like project: packages with nested source directories, build output that
+/// is git ignored, and dependencies that are never scanned.
+fn create_project(base: &Path) {
+    fs::write(base.join(".gitignore"), "dist/\nnode_modules/\n*.log\n").unwrap();
+
+    for package in 0..20 {
+        let package = base.join(format!("packages/package-{}", package));
+
+        for dir in 0..10 {
+            let dir = package.join(format!("src/components/dir-{}", dir));
+            fs::create_dir_all(&dir).unwrap();
+
+            for file in 0..20 {
+                fs::write(
+                    dir.join(format!("component-{}.tsx", file)),
+                    "<div className=\"flex\"></div>",
+                )
+                .unwrap();
+                fs::write(dir.join(format!("component-{}.log", file)), "").unwrap();
+            }
+        }
+
+        let dist = package.join("dist");
+        fs::create_dir_all(&dist).unwrap();
+        for file in 0..50 {
+            fs::write(dist.join(format!("chunk-{}.js", file)), "").unwrap();
+        }
+
+        let node_modules = package.join("node_modules/dependency");
+        fs::create_dir_all(&node_modules).unwrap();
+        for file in 0..50 {
+            fs::write(node_modules.join(format!("index-{}.js", file)), "").unwrap();
+        }
+    }
+}
+
+/// Run `f` a number of times and print the mean and fastest run
+fn bench<T>(name: &str, iterations: u32, mut f: impl FnMut() -> T) {
+    // Warm up the file system cache
+    std::hint::black_box(f());
+
+    let mut total = Duration::ZERO;
+    let mut fastest = Duration::MAX;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        std::hint::black_box(f());
+        let elapsed = start.elapsed();
+
+        total += elapsed;
+        fastest = fastest.min(elapsed);
+    }
+
+    println!(
+        "{:<24} mean: {:>10.2?}   fastest: {:>10.2?}   ({} iterations)",
+        name,
+        total / iterations,
+        fastest,
+        iterations
+    );
+}
+
+/// The files and the bases of the globs that source detection used to find, by walking the base
+/// three times: once with the gitignore-aware walker, once more to find directories with ignored
+/// files, and a final time to classify directories for globs. The extensions of the globs are left
+/// out, they don't need another walk.
+fn detect_with_multiple_walks(base: &Path, rules: &IgnoreRules) -> (Vec<PathBuf>, Vec<String>) {
+    let mut files: Vec<PathBuf> = vec![];
+    let mut dirs: Vec<PathBuf> = vec![];
+
+    // The first walk: all allowed files and directories
+    let walk_rules = rules.clone();
+    let entries = WalkBuilder::new(base)
+        .hidden(false)
+        .require_git(false)
+        .add_custom_ignore_filename(TAILWIND_IGNORE_FILE)
+        .filter_entry(move |entry| match entry.file_type() {
+            Some(file_type) if file_type.is_dir() => match entry.file_name().to_str() {
+                Some(dir) => !walk_rules.is_ignored_dir(dir),
+                None => false,
+            },
+            Some(file_type) if file_type.is_file() || file_type.is_symlink() => {
+                walk_rules.is_allowed_content_file(entry.path())
+            }
+            _ => false,
+        })
+        .build();
+
+    for entry in entries.filter_map(Result::ok) {
+        match entry.file_type() {
+            Some(file_type) if file_type.is_file() => files.push(entry.into_path()),
+            Some(file_type) if file_type.is_dir() => dirs.push(entry.into_path()),
+            _ => {}
+        }
+    }
+
+    let allowed_paths: FxHashSet<&Path> = dirs.iter().map(PathBuf::as_path).collect();
+    let allowed_files: FxHashSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+
+    // The second walk: read every allowed directory again, to find the ones with ignored files
+    let mut ignored_file_directories: FxHashSet<&Path> = FxHashSet::default();
+    for dir in &dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        let has_ignored_files = entries.filter_map(Result::ok).any(|entry| {
+            let path = entry.path();
+
+            entry.file_type().is_ok_and(|file_type| file_type.is_file())
+                && rules.is_allowed_content_path(&path)
+                && !allowed_files.contains(path.as_path())
+        });
+
+        if has_ignored_files {
+            ignored_file_directories
+                .extend(dir.ancestors().take_while(|dir| dir.starts_with(base)));
+        }
+    }
+
+    // The third walk: classify the directories for globs
+    let mut forced_static_directories = vec![base.join("public")];
+    let mut deep_globable_directories: FxHashSet<PathBuf> = FxHashSet::default();
+    let mut shallow_globable_directories: FxHashSet<PathBuf> = FxHashSet::default();
+
+    let mut it = WalkDir::new(base)
+        .sort_by(
+            |a, z| match (a.file_type().is_dir(), z.file_type().is_dir()) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => a.file_name().cmp(z.file_name()),
+            },
+        )
+        .into_iter();
+
+    while let Some(Ok(entry)) = it.next() {
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+
+        let path = entry.path();
+
+        if entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| rules.is_ignored_dir(name))
+        {
+            it.skip_current_dir();
+            continue;
+        }
+
+        if forced_static_directories.iter().any(|dir| dir == path)
+            || forced_static_directories
+                .iter()
+                .any(|dir| Some(dir.as_path()) == path.parent())
+        {
+            forced_static_directories.push(path.to_path_buf());
+            continue;
+        }
+
+        if !allowed_paths.contains(path) {
+            let mut parent = path.parent();
+            while let Some(parent_path) = parent {
+                if deep_globable_directories.remove(parent_path) {
+                    shallow_globable_directories.insert(parent_path.to_path_buf());
+                }
+
+                if parent_path == base {
+                    break;
+                }
+
+                shallow_globable_directories.insert(parent_path.to_path_buf());
+                parent = parent_path.parent();
+            }
+
+            it.skip_current_dir();
+            continue;
+        }
+
+        let found_deep_glob_parent = path
+            .ancestors()
+            .skip(1)
+            .take_while(|parent| *parent != base)
+            .any(|parent| deep_globable_directories.contains(parent));
+
+        if !found_deep_glob_parent && path != base && !ignored_file_directories.contains(path) {
+            deep_globable_directories.insert(path.to_path_buf());
+        }
+    }
+
+    let mut glob_bases: Vec<String> = shallow_globable_directories
+        .iter()
+        .chain(&deep_globable_directories)
+        .map(|dir| dir.display().to_string())
+        .collect();
+    glob_bases.sort();
+    files.sort();
+
+    (files, glob_bases)
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = dunce::canonicalize(dir.path()).unwrap();
+    create_project(&base);
+
+    let detect_sources = DetectSources::new(base.clone());
+    let rules = IgnoreRules::default();
+
+    // Both should detect the same sources, or the comparison is meaningless
+    let (mut files, globs, _) = detect_sources.detect();
+    let mut glob_bases: Vec<String> = globs.into_iter().map(|glob| glob.base).collect();
+    files.sort();
+    glob_bases.sort();
+    assert_eq!(
+        (files, glob_bases),
+        detect_with_multiple_walks(&base, &rules)
+    );
+
+    bench("multiple walks (before)", 20, || {
+        detect_with_multiple_walks(&base, &rules)
+    });
+    bench("single walk (after)", 20, || detect_sources.detect());
+}
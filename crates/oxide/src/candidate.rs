@@ -0,0 +1,412 @@
+/// A candidate split into its structural parts, e.g.: `hover:-bg-red-500/50!`
+///
+/// Oxide has no knowledge of the design system, which means that we can't know where the root of
+/// a named utility ends. For named values we split at the first `-`, e.g.: `bg-red-500` has the
+/// root `bg` and the named value `red-500`. Use `Candidate::roots` to get every possible split.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The candidate as it was written
+    pub raw: String,
+
+    /// Variants in the order they are written, e.g.: `["md", "hover"]` for `md:hover:flex`
+    pub variants: Vec<String>,
+
+    pub kind: CandidateKind,
+
+    /// The utility root, e.g.: `bg` for `bg-red-500`, or the property for arbitrary properties,
+    /// e.g.: `color` for `[color:red]`
+    pub root: String,
+
+    pub value: Option<CandidateValue>,
+
+    /// The modifier after the `/`, e.g.: `50` for `bg-red-500/50`
+    pub modifier: Option<CandidateValue>,
+
+    /// The end of the value and the modifier read as a fraction, e.g.: `1/2` for `w-1/2` or
+    /// `-translate-x-1/2`. The rest of the value is part of the root in that reading, e.g.:
+    /// `translate-x`. Without the design system we can't know whether the utility takes a fraction
+    /// or a modifier, so both readings are kept when the value ends with a number and the modifier
+    /// is a number.
+    pub fraction: Option<String>,
+
+    /// Whether the utility is negated, e.g.: `-mx-4`
+    pub negative: bool,
+
+    /// Whether the utility is marked as important, e.g.: `!flex` or `flex!`
+    pub important: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateKind {
+    /// A utility with or without a value, e.g.: `flex`, `bg-red-500` or `bg-[#0088cc]`
+    Utility,
+
+    /// An arbitrary property, e.g.: `[color:red]`
+    ArbitraryProperty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidateValue {
+    /// A named value, e.g.: `red-500` in `bg-red-500`
+    Named(String),
+
+    /// An arbitrary value as written, e.g.: `#0088cc` in `bg-[#0088cc]`. CSS variable shorthands
+    /// are normalized, e.g.: `var(--brand)` for `bg-(--brand)`.
+    Arbitrary {
+        value: String,
+
+        /// The type hint, e.g.: `color` in `bg-[color:var(--brand)]` or `bg-(color:--brand)`
+        data_type: Option<String>,
+    },
+}
+
+impl Candidate {
+    /// Parse a candidate string into its parts. Returns `None` when there is no utility, e.g.:
+    /// `hover:`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut variants = segment(input, b':');
+
+        // Safety: `segment` always returns at least one part
+        let mut utility = variants.pop().unwrap();
+
+        let mut important = false;
+        if let Some(rest) = utility.strip_suffix('!') {
+            important = true;
+            utility = rest;
+        } else if let Some(rest) = utility.strip_prefix('!') {
+            important = true;
+            utility = rest;
+        }
+
+        // Only a single `!` is allowed, e.g.: `!flex!` is not `!flex` marked as important
+        if utility.is_empty()
+            || utility.starts_with('!')
+            || utility.ends_with('!')
+            || variants.iter().any(|variant| variant.is_empty())
+        {
+            return None;
+        }
+
+        let variants = variants.into_iter().map(Into::into).collect();
+
+        // Arbitrary properties, e.g.: `[color:red]`
+        if let Some(inner) = utility
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let (property, value) = inner.split_once(':')?;
+
+            return Some(Self {
+                raw: input.into(),
+                variants,
+                kind: CandidateKind::ArbitraryProperty,
+                root: property.into(),
+                value: Some(CandidateValue::Arbitrary {
+                    value: value.into(),
+                    data_type: None,
+                }),
+                modifier: None,
+                fraction: None,
+                negative: false,
+                important,
+            });
+        }
+
+        let mut negative = false;
+        if let Some(rest) = utility.strip_prefix('-') {
+            negative = true;
+            utility = rest;
+        }
+
+        let mut parts = segment(utility, b'/');
+        let modifier = match parts.len() {
+            1 => None,
+            2 => Some(CandidateValue::parse(parts.pop().unwrap())?),
+            // Only a single modifier is allowed
+            _ => return None,
+        };
+        let utility = parts.pop().unwrap();
+
+        let (root, value) = match utility.find(['[', '(']) {
+            // Arbitrary values and CSS variable shorthands, e.g.: `bg-[#0088cc]` or `bg-(--brand)`
+            Some(idx) => {
+                let root = utility[..idx].strip_suffix('-')?;
+                (root, Some(CandidateValue::parse(&utility[idx..])?))
+            }
+
+            // Named values, e.g.: `bg-red-500`
+            None => match utility.split_once('-') {
+                Some((root, value)) if !root.is_empty() && !value.is_empty() => {
+                    (root, Some(CandidateValue::Named(value.into())))
+                }
+                _ => (utility, None),
+            },
+        };
+
+        if root.is_empty() {
+            return None;
+        }
+
+        let fraction = match (&value, &modifier) {
+            (Some(CandidateValue::Named(value)), Some(CandidateValue::Named(denominator))) => {
+                let numerator = value.rsplit('-').next().unwrap_or(value);
+
+                match is_number(numerator) && is_number(denominator) {
+                    true => Some(format!("{}/{}", numerator, denominator)),
+                    false => None,
+                }
+            }
+            _ => None,
+        };
+
+        Some(Self {
+            raw: input.into(),
+            variants,
+            kind: CandidateKind::Utility,
+            root: root.into(),
+            value,
+            modifier,
+            fraction,
+            negative,
+            important,
+        })
+    }
+
+    /// Every possible root and named value combination, from the longest root to the shortest,
+    /// e.g.: `bg-red-500`, `bg-red` with `500` and `bg` with `red-500`. Candidates with arbitrary
+    /// values only have a single root.
+    pub fn roots(&self) -> Vec<(String, Option<CandidateValue>)> {
+        let Some(CandidateValue::Named(value)) = &self.value else {
+            return vec![(self.root.clone(), self.value.clone())];
+        };
+
+        let full = format!("{}-{}", self.root, value);
+        let mut roots = vec![(full.clone(), None)];
+        for (idx, _) in full.rmatch_indices('-') {
+            if idx == 0 || idx + 1 == full.len() {
+                continue;
+            }
+
+            roots.push((
+                full[..idx].to_string(),
+                Some(CandidateValue::Named(full[idx + 1..].to_string())),
+            ));
+        }
+
+        roots
+    }
+}
+
+impl CandidateValue {
+    fn parse(input: &str) -> Option<Self> {
+        if let Some(inner) = input
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            if inner.is_empty() {
+                return None;
+            }
+
+            let (data_type, value) = split_data_type(inner);
+
+            return Some(Self::Arbitrary {
+                value: value.into(),
+                data_type: data_type.map(Into::into),
+            });
+        }
+
+        if let Some(inner) = input
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let (data_type, value) = split_data_type(inner);
+            if !value.starts_with("--") {
+                return None;
+            }
+
+            return Some(Self::Arbitrary {
+                value: format!("var({})", value),
+                data_type: data_type.map(Into::into),
+            });
+        }
+
+        if input.is_empty() || input.contains(['[', ']', '(', ')']) {
+            return None;
+        }
+
+        Some(Self::Named(input.into()))
+    }
+}
+
+fn is_number(input: &str) -> bool {
+    !input.is_empty() && input.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// Split a type hint from an arbitrary value, e.g.: `color:red` becomes `(Some("color"), "red")`.
+fn split_data_type(input: &str) -> (Option<&str>, &str) {
+    match input.split_once(':') {
+        Some((data_type, value))
+            if !data_type.is_empty()
+                && data_type.starts_with(|c: char| c.is_ascii_lowercase())
+                && data_type
+                    .bytes()
+                    .all(|c| c.is_ascii_lowercase() || c == b'-') =>
+        {
+            (Some(data_type), value)
+        }
+        _ => (None, input),
+    }
+}
+
+/// Split the input on the separator, but only when the separator is not inside of brackets or
+/// parentheses.
+fn segment(input: &str, separator: u8) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (idx, c) in input.bytes().enumerate() {
+        match c {
+            b'[' | b'(' => depth += 1,
+            b']' | b')' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&input[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&input[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(value: &str) -> Option<CandidateValue> {
+        Some(CandidateValue::Named(value.into()))
+    }
+
+    fn arbitrary(value: &str, data_type: Option<&str>) -> Option<CandidateValue> {
+        Some(CandidateValue::Arbitrary {
+            value: value.into(),
+            data_type: data_type.map(Into::into),
+        })
+    }
+
+    #[test]
+    fn it_parses_static_utilities() {
+        let candidate = Candidate::parse("flex").unwrap();
+        assert_eq!(candidate.root, "flex");
+        assert_eq!(candidate.value, None);
+        assert!(candidate.variants.is_empty());
+        assert_eq!(candidate.kind, CandidateKind::Utility);
+    }
+
+    #[test]
+    fn it_parses_variants_negative_and_important() {
+        let candidate = Candidate::parse("md:hover:-mx-4!").unwrap();
+        assert_eq!(candidate.variants, vec!["md", "hover"]);
+        assert_eq!(candidate.root, "mx");
+        assert_eq!(candidate.value, named("4"));
+        assert!(candidate.negative);
+        assert!(candidate.important);
+
+        let candidate = Candidate::parse("!-mx-4").unwrap();
+        assert!(candidate.negative);
+        assert!(candidate.important);
+    }
+
+    #[test]
+    fn it_parses_arbitrary_variants() {
+        let candidate = Candidate::parse("[&:hover]:group-[.is-open]:underline").unwrap();
+        assert_eq!(candidate.variants, vec!["[&:hover]", "group-[.is-open]"]);
+        assert_eq!(candidate.root, "underline");
+    }
+
+    #[test]
+    fn it_parses_arbitrary_values_and_modifiers() {
+        let candidate = Candidate::parse("bg-[color:var(--brand)]/[50%]").unwrap();
+        assert_eq!(candidate.root, "bg");
+        assert_eq!(candidate.value, arbitrary("var(--brand)", Some("color")));
+        assert_eq!(candidate.modifier, arbitrary("50%", None));
+
+        let candidate = Candidate::parse("bg-red-500/50").unwrap();
+        assert_eq!(candidate.root, "bg");
+        assert_eq!(candidate.value, named("red-500"));
+        assert_eq!(candidate.modifier, named("50"));
+    }
+
+    #[test]
+    fn it_parses_fractions() {
+        let candidate = Candidate::parse("w-1/2").unwrap();
+        assert_eq!(candidate.root, "w");
+        assert_eq!(candidate.value, named("1"));
+        assert_eq!(candidate.modifier, named("2"));
+        assert_eq!(candidate.fraction, Some("1/2".into()));
+
+        let candidate = Candidate::parse("-translate-x-1/2").unwrap();
+        assert!(candidate.negative);
+        assert_eq!(candidate.root, "translate");
+        assert_eq!(candidate.value, named("x-1"));
+        assert_eq!(candidate.fraction, Some("1/2".into()));
+
+        // Both readings are kept, because the modifier could be a fraction as well
+        let candidate = Candidate::parse("bg-red-500/50").unwrap();
+        assert_eq!(candidate.fraction, Some("500/50".into()));
+
+        let candidate = Candidate::parse("bg-red/50").unwrap();
+        assert_eq!(candidate.fraction, None);
+
+        let candidate = Candidate::parse("w-1/[2]").unwrap();
+        assert_eq!(candidate.fraction, None);
+    }
+
+    #[test]
+    fn it_parses_css_variable_shorthands() {
+        let candidate = Candidate::parse("hover:text-(--accent)/(--alpha)").unwrap();
+        assert_eq!(candidate.root, "text");
+        assert_eq!(candidate.value, arbitrary("var(--accent)", None));
+        assert_eq!(candidate.modifier, arbitrary("var(--alpha)", None));
+
+        let candidate = Candidate::parse("bg-(color:--brand)").unwrap();
+        assert_eq!(candidate.value, arbitrary("var(--brand)", Some("color")));
+    }
+
+    #[test]
+    fn it_parses_arbitrary_properties() {
+        let candidate = Candidate::parse("lg:[--my-var:1_/_2]!").unwrap();
+        assert_eq!(candidate.kind, CandidateKind::ArbitraryProperty);
+        assert_eq!(candidate.variants, vec!["lg"]);
+        assert_eq!(candidate.root, "--my-var");
+        assert_eq!(candidate.value, arbitrary("1_/_2", None));
+        assert!(candidate.important);
+    }
+
+    #[test]
+    fn it_rejects_invalid_candidates() {
+        assert_eq!(Candidate::parse(""), None);
+        assert_eq!(Candidate::parse("hover:"), None);
+        assert_eq!(Candidate::parse(":flex"), None);
+        assert_eq!(Candidate::parse("bg-red/50/50"), None);
+        assert_eq!(Candidate::parse("bg-(brand)"), None);
+        assert_eq!(Candidate::parse("[color]"), None);
+        assert_eq!(Candidate::parse("!flex!"), None);
+        assert_eq!(Candidate::parse("-[color:red]"), None);
+    }
+
+    #[test]
+    fn it_lists_all_possible_roots() {
+        let candidate = Candidate::parse("bg-red-500").unwrap();
+        assert_eq!(
+            candidate.roots(),
+            vec![
+                ("bg-red-500".to_string(), None),
+                ("bg-red".to_string(), named("500")),
+                ("bg".to_string(), named("red-500")),
+            ]
+        );
+    }
+}
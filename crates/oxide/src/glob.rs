@@ -1,22 +1,56 @@
-use glob_match::glob_match;
 use std::iter;
 use std::path::{Path, PathBuf};
 
-use crate::GlobEntry;
+use crate::{GlobEntry, ScanError};
 
+/// Walk all files that match the patterns. Invalid patterns are reported, and don't prevent
+/// walking the other patterns.
 pub fn fast_glob(
     patterns: &Vec<GlobEntry>,
-) -> Result<impl iter::Iterator<Item = PathBuf>, std::io::Error> {
-    Ok(get_fast_patterns(patterns)
-        .into_iter()
-        .flat_map(|(base_path, patterns)| {
-            globwalk::GlobWalkerBuilder::from_patterns(base_path, &patterns)
-                .follow_links(true)
-                .build()
-                .unwrap()
-                .filter_map(Result::ok)
-                .map(|file| file.path().to_path_buf())
-        }))
+) -> (impl iter::Iterator<Item = PathBuf>, Vec<ScanError>) {
+    let mut errors = vec![];
+    let mut walkers = vec![];
+
+    for (base_path, patterns) in get_fast_patterns(patterns) {
+        let invalid_glob = |err: globwalk::GlobError| ScanError::InvalidGlob {
+            base: base_path.clone(),
+            message: err.to_string(),
+        };
+
+        // Compile every pattern on its own, so that we know which ones are invalid
+        let patterns: Vec<String> = patterns
+            .into_iter()
+            .filter(|pattern| {
+                match globwalk::GlobWalkerBuilder::from_patterns(&base_path, &[pattern]).build() {
+                    Ok(_) => true,
+                    Err(err) => {
+                        errors.push(invalid_glob(err));
+                        false
+                    }
+                }
+            })
+            .collect();
+
+        if patterns.is_empty() {
+            continue;
+        }
+
+        match globwalk::GlobWalkerBuilder::from_patterns(&base_path, &patterns)
+            .follow_links(true)
+            .build()
+        {
+            Ok(walker) => walkers.push(walker),
+            Err(err) => errors.push(invalid_glob(err)),
+        }
+    }
+
+    let files = walkers.into_iter().flat_map(|walker| {
+        walker
+            .filter_map(Result::ok)
+            .map(|file| file.path().to_path_buf())
+    });
+
+    (files, errors)
 }
 
 /// This function attempts to optimize the glob patterns to improve performance. The problem is
@@ -47,202 +81,406 @@ pub fn get_fast_patterns(patterns: &Vec<GlobEntry>) -> Vec<(PathBuf, Vec<String>
 
     for pattern in patterns {
         let base_path = PathBuf::from(&pattern.base);
-        let pattern = &pattern.pattern;
 
-        let is_negated = pattern.starts_with('!');
-        let mut pattern = pattern.clone();
+        let (pattern, is_negated) = match pattern.pattern.strip_prefix('!') {
+            Some(pattern) => (pattern, true),
+            None => (pattern.pattern.as_str(), false),
+        };
+
+        let mut folders = split_segments(pattern);
+
+        // The file pattern is always kept as-is, even when it doesn't contain any glob magic.
+        // Safety: Splitting always results in at least one segment, so we can safely unwrap.
+        let file_pattern = folders.pop().unwrap();
+
+        let mut base_paths = vec![base_path];
+        let mut remaining_folders: &[String] = &[];
+
+        for (i, folder) in folders.iter().enumerate() {
+            // The folder is a simple folder name, or it uses an expandable pattern that expands
+            // into simple folder names. We should be able to safely add it to the existing paths.
+            if let Some(branches) = static_branches(folder) {
+                base_paths = branches
+                    .iter()
+                    .flat_map(|branch| {
+                        base_paths
+                            .iter()
+                            .map(|path| join_segments(path, branch))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+            }
+            // There is a wildcard in the folder, so we have to bail now... 😢 But this also means
+            // that we can skip looking at the rest of the folders, so there is at least this small
+            // optimization we can apply!
+            else {
+                remaining_folders = &folders[i..];
+                break;
+            }
+        }
+
+        // Get all the remaining folders, attach the existing file_pattern so that this can now be
+        // the final pattern we use.
+        let mut pattern = remaining_folders.to_vec();
+        pattern.push(file_pattern);
+        let pattern = pattern.join("/");
+
+        // The globwalk library doesn't understand nested braces and ranges, so expand them here.
+        let mut patterns = match has_extended_braces(&pattern) {
+            true => expand_braces(&pattern),
+            false => vec![pattern],
+        };
+
+        // Ensure that we re-add the `!` sign to the patterns of this glob.
         if is_negated {
-            pattern.remove(0);
+            for pattern in &mut patterns {
+                pattern.insert(0, '!');
+            }
         }
 
-        let mut folders = pattern.split('/').collect::<Vec<_>>();
-
-        if folders.len() <= 1 {
-            // No paths we can simplify, so let's use it as-is.
-            optimized_patterns.push((base_path, vec![pattern]));
-        } else {
-            // We do have folders because `/` exists. Let's try to simplify the globs!
-            // Safety: We know that the length is greater than 1, so we can safely unwrap.
-            let file_pattern = folders.pop().unwrap();
-            let all_folders = folders.clone();
-            let mut temp_paths = vec![base_path];
-
-            let mut bail = false;
-
-            for (i, folder) in folders.into_iter().enumerate() {
-                // There is a wildcard in the folder, so we have to bail now... 😢 But this also
-                // means that we can skip looking at the rest of the folders, so there is at least
-                // this small optimization we can apply!
-                if folder.contains('*') {
-                    // Get all the remaining folders, attach the existing file_pattern so that this
-                    // can now be the final pattern we use.
-                    let mut remaining_folders = all_folders[i..].to_vec();
-                    remaining_folders.push(file_pattern);
-
-                    let pattern = remaining_folders.join("/");
-                    for path in &temp_paths {
-                        optimized_patterns.push((path.to_path_buf(), vec![pattern.to_string()]));
-                    }
+        for path in base_paths {
+            optimized_patterns.push((path, patterns.clone()));
+        }
+    }
 
-                    bail = true;
-                    break;
-                }
+    optimized_patterns
+}
 
-                // The folder is very likely using an expandable pattern which we can expand!
-                if folder.contains('{') && folder.contains('}') {
-                    let branches = expand_braces(folder);
+/// Split the pattern into folders. A `/` that is escaped, or inside of braces, doesn't separate
+/// folders. Empty folders, e.g.: in `a//b`, are dropped.
+fn split_segments(pattern: &str) -> Vec<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut segments = split_top_level(&chars, '/');
 
-                    let existing_paths = temp_paths;
-                    temp_paths = branches
-                        .iter()
-                        .flat_map(|branch| {
-                            existing_paths
-                                .clone()
-                                .into_iter()
-                                .map(|path| path.join(branch))
-                                .collect::<Vec<_>>()
-                        })
-                        .collect::<Vec<_>>();
-                }
-                // The folder should just be a simple folder name without any glob magic. We should
-                // be able to safely add it to the existing paths.
+    // Keep the file pattern, even when it's empty
+    let file_pattern = segments.pop().unwrap_or_default();
+    segments.retain(|segment| !segment.is_empty() && segment != ".");
+    segments.push(file_pattern);
+
+    segments
+}
+
+/// The folder names the folder pattern expands to, or `None` when the folder contains glob magic
+/// that can't be expanded, e.g.: `*`, `?` or `[abc]`.
+fn static_branches(folder: &str) -> Option<Vec<String>> {
+    expand_braces(folder)
+        .into_iter()
+        .map(|branch| unescape(&branch))
+        .collect()
+}
+
+/// Remove the escapes from a pattern without any glob magic. Returns `None` when the pattern does
+/// contain glob magic.
+fn unescape(pattern: &str) -> Option<String> {
+    let mut result = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.push(chars.next()?),
+            '*' | '?' | '[' | ']' | '{' | '}' => return None,
+            _ => result.push(c),
+        }
+    }
+
+    Some(result)
+}
+
+/// Join the folders to the path. Braces can contain a `/`, e.g.: `{src,lib/ui}`, so a single
+/// branch can contain multiple folders.
+fn join_segments(path: &Path, folders: &str) -> PathBuf {
+    folders
+        .split('/')
+        .filter(|folder| !folder.is_empty() && *folder != ".")
+        .fold(path.to_path_buf(), |path, folder| path.join(folder))
+}
+
+/// A precompiled set of globs, to check whether paths match any of them. A path matches when it
+/// matches any of the globs, unless it also matches a negated (`!`) glob.
+#[derive(Debug, Clone)]
+pub struct GlobSet {
+    include: globset::GlobSet,
+    exclude: globset::GlobSet,
+}
+
+impl GlobSet {
+    /// Compile the globs. Invalid globs never match, they are reported when the sources are
+    /// resolved instead.
+    pub fn new(globs: &[GlobEntry]) -> Self {
+        let mut include = globset::GlobSetBuilder::new();
+        let mut exclude = globset::GlobSetBuilder::new();
+
+        for glob in globs {
+            let (pattern, is_negated) = match glob.pattern.strip_prefix('!') {
+                Some(pattern) => (pattern, true),
+                None => (glob.pattern.as_str(), false),
+            };
+
+            // The base is a path, so characters like `[` in `app/[slug]` are not glob syntax
+            let base = glob.base.replace(std::path::MAIN_SEPARATOR, "/");
+            let base = globset::escape(base.trim_end_matches('/'));
+
+            // Expand what `globset` can't handle itself
+            let patterns = match has_extended_braces(pattern) {
+                true => expand_braces(pattern),
+                false => vec![pattern.to_string()],
+            };
+
+            for pattern in patterns {
+                let Ok(compiled) = globset::GlobBuilder::new(&format!("{}/{}", base, pattern))
+                    .literal_separator(true)
+                    .build()
                 else {
-                    temp_paths = temp_paths
-                        .into_iter()
-                        .map(|path| path.join(folder))
-                        .collect();
-                }
-            }
+                    continue;
+                };
 
-            // As long as we didn't bail, we can now add the current expanded patterns to the
-            // optimized patterns.
-            if !bail {
-                for path in &temp_paths {
-                    optimized_patterns.push((path.to_path_buf(), vec![file_pattern.to_string()]));
-                }
+                match is_negated {
+                    true => exclude.add(compiled),
+                    false => include.add(compiled),
+                };
             }
         }
 
-        // Ensure that we re-add all the `!` signs to the patterns.
-        if is_negated {
-            for (_, patterns) in &mut optimized_patterns {
-                for pattern in patterns {
-                    pattern.insert(0, '!');
-                }
-            }
+        Self {
+            include: include
+                .build()
+                .unwrap_or_else(|_| globset::GlobSet::empty()),
+            exclude: exclude
+                .build()
+                .unwrap_or_else(|_| globset::GlobSet::empty()),
         }
     }
 
-    optimized_patterns
-}
-
-pub fn path_matches_globs(path: &Path, globs: &[GlobEntry]) -> bool {
-    let path = path.to_string_lossy();
+    pub fn is_match(&self, path: &Path) -> bool {
+        let candidate = globset::Candidate::new(path);
 
-    globs
-        .iter()
-        .any(|g| glob_match(&format!("{}/{}", g.base, g.pattern), &path))
+        self.include.is_match_candidate(&candidate) && !self.exclude.is_match_candidate(&candidate)
+    }
 }
 
+/// The maximum number of patterns that a single pattern expands to. Braces that would expand to
+/// more, e.g.: `{1..100000}`, are kept as-is.
+const MAX_EXPANSIONS: usize = 1024;
+
+/// Expand all braces in the pattern. Braces can be nested, and can contain numeric or character
+/// ranges. Escaped characters are kept as-is, and braces without a closing brace are not expanded.
+///
 /// Given this input: a-{b,c}-d-{e,f}
 /// We will get:
 /// [
 ///   a-b-d-e
-///   a-b-d-f
 ///   a-c-d-e
+///   a-b-d-f
 ///   a-c-d-f
 /// ]
-/// TODO: There is probably a way nicer way of doing this, but this works for now.
+///
+/// And given this input: a-{1..3}
+/// We will get:
+/// [
+///   a-1
+///   a-2
+///   a-3
+/// ]
 fn expand_braces(input: &str) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
+    expand(&input.chars().collect::<Vec<_>>())
+}
 
-    let mut in_braces = false;
-    let mut last_char: char = '\0';
+fn expand(chars: &[char]) -> Vec<String> {
+    let mut result = vec![String::new()];
+    let mut i = 0;
 
-    let mut current = String::new();
+    while i < chars.len() {
+        let literal = match chars[i] {
+            // Keep the escape, so that the result is still a valid pattern
+            '\\' => &chars[i..(i + 2).min(chars.len())],
 
-    // Given the input: a-{b,c}-d-{e,f}-g
-    // The template will look like this: ["a-", "-d-", "g"].
-    let mut template: Vec<String> = vec![];
+            '{' => match closing_brace(chars, i) {
+                Some(end) => {
+                    let branches = expand_group(&chars[i + 1..end]);
 
-    // The branches will look like this: [["b", "c"], ["e", "f"]].
-    let mut branches: Vec<Vec<String>> = vec![];
+                    if result.len() * branches.len() > MAX_EXPANSIONS {
+                        for x in &mut result {
+                            x.extend(&chars[i..=end]);
+                        }
 
-    for (i, c) in input.char_indices() {
-        let is_escaped = i > 0 && last_char == '\\';
-        last_char = c;
+                        i = end + 1;
+                        continue;
+                    }
 
-        match c {
-            '{' if !is_escaped => {
-                // Ensure that when a new set of braces is opened, that we at least have 1
-                // template.
-                if template.is_empty() {
-                    template.push(String::new());
-                }
+                    // Copy the existing results for every single branch.
+                    result = branches
+                        .iter()
+                        .flat_map(|branch| result.iter().map(move |x| format!("{}{}", x, branch)))
+                        .collect();
 
-                in_braces = true;
-                branches.push(vec![]);
-                template.push(String::new());
-            }
-            '}' if !is_escaped => {
-                in_braces = false;
-                if let Some(last) = branches.last_mut() {
-                    last.push(current.clone());
-                }
-                current.clear();
-            }
-            ',' if !is_escaped && in_braces => {
-                if let Some(last) = branches.last_mut() {
-                    last.push(current.clone());
-                }
-                current.clear();
-            }
-            _ if in_braces => current.push(c),
-            _ => {
-                if template.is_empty() {
-                    template.push(String::new());
+                    i = end + 1;
+                    continue;
                 }
+                None => &chars[i..i + 1],
+            },
 
-                if let Some(last) = template.last_mut() {
-                    last.push(c);
-                }
-            }
+            _ => &chars[i..i + 1],
         };
+
+        for x in &mut result {
+            x.extend(literal);
+        }
+
+        i += literal.len();
     }
 
-    // Ensure we have a string that we can start adding information too.
-    if !template.is_empty() && !branches.is_empty() {
-        result.push("".to_string());
+    result
+}
+
+/// Expand the content of a set of braces, e.g.: `a,b` or `1..3`
+fn expand_group(chars: &[char]) -> Vec<String> {
+    if let Some(range) = expand_range(&chars.iter().collect::<String>()) {
+        return range;
     }
 
-    // Let's try to generate everything!
-    for (i, template) in template.into_iter().enumerate() {
-        // Append current template string to all existing results.
-        result = result.into_iter().map(|x| x + &template).collect();
-
-        // Get the results, and copy it for every single branch.
-        if let Some(branches) = branches.get(i) {
-            result = branches
-                .iter()
-                .flat_map(|branch| {
-                    result
-                        .clone()
-                        .into_iter()
-                        .map(|x| x + branch)
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>();
+    split_top_level(chars, ',')
+        .iter()
+        .flat_map(|branch| expand_braces(branch))
+        .collect()
+}
+
+/// Expand a numeric range like `1..3` or `01..10`, or a character range like `a..c`. Ranges can
+/// count down as well, e.g.: `3..1`. Ranges that are too large are kept as-is, in braces.
+fn expand_range(range: &str) -> Option<Vec<String>> {
+    let (start, end) = range.split_once("..")?;
+
+    if let (Ok(from), Ok(to)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        if from.abs_diff(to) >= MAX_EXPANSIONS as u64 {
+            return Some(vec![format!("{{{}}}", range)]);
         }
+
+        // Numbers are padded with zeros when one of them is, e.g.: `01..10`
+        let is_padded = |x: &str| {
+            x.trim_start_matches('-').len() > 1 && x.trim_start_matches('-').starts_with('0')
+        };
+        let width = match is_padded(start) || is_padded(end) {
+            true => start.len().max(end.len()),
+            false => 0,
+        };
+
+        let numbers: Vec<i64> = match from <= to {
+            true => (from..=to).collect(),
+            false => (to..=from).rev().collect(),
+        };
+
+        return Some(
+            numbers
+                .into_iter()
+                .map(|x| format!("{:0width$}", x, width = width))
+                .collect(),
+        );
+    }
+
+    let mut start = start.chars();
+    let mut end = end.chars();
+    match (start.next(), start.next(), end.next(), end.next()) {
+        (Some(from), None, Some(to), None)
+            if from.is_ascii_alphabetic() && to.is_ascii_alphabetic() =>
+        {
+            let chars: Vec<char> = match from <= to {
+                true => (from..=to).collect(),
+                false => (to..=from).rev().collect(),
+            };
+
+            Some(chars.into_iter().map(String::from).collect())
+        }
+        _ => None,
+    }
+}
+
+/// The position of the brace that closes the brace at `open`, if any
+fn closing_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// Split on the separator, unless it is escaped or inside of braces
+fn split_top_level(chars: &[char], separator: char) -> Vec<String> {
+    let mut result = vec![String::new()];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == separator {
+            result.push(String::new());
+            i += 1;
+            continue;
+        }
+
+        let end = match c {
+            '\\' => (i + 2).min(chars.len()),
+            '{' => closing_brace(chars, i).map_or(i + 1, |end| end + 1),
+            _ => i + 1,
+        };
+
+        if let Some(last) = result.last_mut() {
+            last.extend(&chars[i..end]);
+        }
+
+        i = end;
     }
 
     result
 }
 
+/// Whether the pattern uses braces that `globset` doesn't understand: nested braces and ranges
+fn has_extended_braces(pattern: &str) -> bool {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => {
+                if let Some(end) = closing_brace(&chars, i) {
+                    let group = &chars[i + 1..end];
+                    if group.contains(&'{')
+                        || expand_range(&group.iter().collect::<String>()).is_some()
+                    {
+                        return true;
+                    }
+
+                    i = end;
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    false
+}
+
 #[cfg(test)]
 mod tests {
-    use super::get_fast_patterns;
+    use super::{expand_braces, get_fast_patterns, GlobSet};
     use crate::GlobEntry;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn it_should_keep_globs_that_start_with_file_wildcards_as_is() {
@@ -476,4 +714,207 @@ mod tests {
 
         assert_eq!(actual, expected,);
     }
+
+    #[test]
+    fn it_should_match_paths_against_a_glob_set() {
+        let glob = |base: &str, pattern: &str| GlobEntry {
+            base: base.to_string(),
+            pattern: pattern.to_string(),
+        };
+
+        let globs = GlobSet::new(&[
+            glob("/projects/app", "**/*.{html,js}"),
+            glob("/projects/app", "!**/*.test.js"),
+            glob("/projects/[slug]", "*.html"),
+            glob("/projects/invalid", "[a-"),
+        ]);
+
+        assert!(globs.is_match(Path::new("/projects/app/index.html")));
+        assert!(globs.is_match(Path::new("/projects/app/src/nested/index.js")));
+        assert!(!globs.is_match(Path::new("/projects/app/index.css")));
+        assert!(!globs.is_match(Path::new("/projects/app/index.test.js")));
+        assert!(!globs.is_match(Path::new("/projects/other/index.html")));
+
+        // The base is not a glob
+        assert!(globs.is_match(Path::new("/projects/[slug]/index.html")));
+        assert!(!globs.is_match(Path::new("/projects/s/index.html")));
+
+        // `*` doesn't match nested folders
+        assert!(!globs.is_match(Path::new("/projects/[slug]/nested/index.html")));
+    }
+
+    #[test]
+    fn it_should_only_negate_the_negated_patterns() {
+        let actual = get_fast_patterns(&vec![
+            GlobEntry {
+                base: "/projects".to_string(),
+                pattern: "src/*.html".to_string(),
+            },
+            GlobEntry {
+                base: "/projects".to_string(),
+                pattern: "!src/*.test.html".to_string(),
+            },
+        ]);
+        let expected = vec![
+            (PathBuf::from("/projects/src"), vec!["*.html".to_string()]),
+            (
+                PathBuf::from("/projects/src"),
+                vec!["!*.test.html".to_string()],
+            ),
+        ];
+
+        assert_eq!(actual, expected,);
+    }
+
+    #[test]
+    fn it_should_stop_at_question_marks_and_character_classes() {
+        let actual = get_fast_patterns(&vec![
+            GlobEntry {
+                base: "/projects".to_string(),
+                pattern: "a/b?/c/*.html".to_string(),
+            },
+            GlobEntry {
+                base: "/projects".to_string(),
+                pattern: "a/[bc]/*.html".to_string(),
+            },
+        ]);
+        let expected = vec![
+            (
+                PathBuf::from("/projects/a"),
+                vec!["b?/c/*.html".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/a"),
+                vec!["[bc]/*.html".to_string()],
+            ),
+        ];
+
+        assert_eq!(actual, expected,);
+    }
+
+    #[test]
+    fn it_should_move_escaped_folders_to_the_path() {
+        let actual = get_fast_patterns(&vec![GlobEntry {
+            base: "/projects".to_string(),
+            pattern: r"app/\[slug\]/\{a\}/*.html".to_string(),
+        }]);
+        let expected = vec![(
+            PathBuf::from("/projects/app/[slug]/{a}"),
+            vec!["*.html".to_string()],
+        )];
+
+        assert_eq!(actual, expected,);
+    }
+
+    #[test]
+    fn it_should_expand_nested_braces_ranges_and_folders_in_braces() {
+        let actual = get_fast_patterns(&vec![GlobEntry {
+            base: "/projects".to_string(),
+            pattern: "{a,b{c,d},lib/ui}/page-{1..2}/*.{html,js}".to_string(),
+        }]);
+        let expected = vec![
+            (
+                PathBuf::from("/projects/a/page-1"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/bc/page-1"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/bd/page-1"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/lib/ui/page-1"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/a/page-2"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/bc/page-2"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/bd/page-2"),
+                vec!["*.{html,js}".to_string()],
+            ),
+            (
+                PathBuf::from("/projects/lib/ui/page-2"),
+                vec!["*.{html,js}".to_string()],
+            ),
+        ];
+
+        assert_eq!(actual, expected,);
+    }
+
+    #[test]
+    fn it_should_expand_ranges_in_the_remaining_pattern() {
+        let actual = get_fast_patterns(&vec![GlobEntry {
+            base: "/projects".to_string(),
+            pattern: "!src/**/page-{1..2}.{html,js}".to_string(),
+        }]);
+        let expected = vec![(
+            PathBuf::from("/projects/src"),
+            vec![
+                "!**/page-1.html".to_string(),
+                "!**/page-2.html".to_string(),
+                "!**/page-1.js".to_string(),
+                "!**/page-2.js".to_string(),
+            ],
+        )];
+
+        assert_eq!(actual, expected,);
+    }
+
+    #[test]
+    fn it_should_expand_braces() {
+        assert_eq!(expand_braces("a-{b,c}"), vec!["a-b", "a-c"]);
+        assert_eq!(expand_braces("{a,{b,c}d}"), vec!["a", "bd", "cd"]);
+        assert_eq!(expand_braces("{1..3}"), vec!["1", "2", "3"]);
+        assert_eq!(expand_braces("{3..1}"), vec!["3", "2", "1"]);
+        assert_eq!(expand_braces("{-1..1}"), vec!["-1", "0", "1"]);
+        assert_eq!(expand_braces("{08..10}"), vec!["08", "09", "10"]);
+        assert_eq!(expand_braces("{a..c}"), vec!["a", "b", "c"]);
+
+        // Escaped braces and braces without a closing brace are kept as-is
+        assert_eq!(expand_braces(r"\{a,b\}"), vec![r"\{a,b\}"]);
+        assert_eq!(expand_braces(r"{a\,b,c}"), vec![r"a\,b", "c"]);
+        assert_eq!(expand_braces("{a,b"), vec!["{a,b"]);
+        assert_eq!(expand_braces("a.b"), vec!["a.b"]);
+    }
+
+    #[test]
+    fn it_should_not_expand_braces_to_too_many_patterns() {
+        assert_eq!(expand_braces("{1..1024}").len(), 1024);
+        assert_eq!(
+            expand_braces("a-{1..100000000000}"),
+            vec!["a-{1..100000000000}"]
+        );
+
+        // The limit is for the whole pattern
+        let patterns = expand_braces("{a,b}-{1..500}-{x,y}");
+        assert_eq!(patterns.len(), 2 * 500);
+        assert_eq!(patterns[0], "a-1-{x,y}");
+
+        assert_eq!(
+            expand_braces("{{1..600},{601..1200}}"),
+            vec!["{{1..600},{601..1200}}"]
+        );
+    }
+
+    #[test]
+    fn it_should_match_extended_braces_in_a_glob_set() {
+        let globs = GlobSet::new(&[GlobEntry {
+            base: "/projects".to_string(),
+            pattern: "{pages,{app,lib}/ui}/page-{1..3}.html".to_string(),
+        }]);
+
+        assert!(globs.is_match(Path::new("/projects/pages/page-1.html")));
+        assert!(globs.is_match(Path::new("/projects/lib/ui/page-3.html")));
+        assert!(!globs.is_match(Path::new("/projects/lib/ui/page-4.html")));
+        assert!(!globs.is_match(Path::new("/projects/lib/page-1.html")));
+    }
 }
@@ -1,21 +1,32 @@
-use crate::parser::Extractor;
+use crate::candidate::Candidate;
+use crate::parser::{Extractor, ExtractorOptions};
+use crate::preprocessors::pre_process_input;
+use crate::scanner::allowed_paths::{
+    is_binary, IgnoreMatcher, IgnoreRules, DEFAULT_IGNORE_RULES, TAILWIND_IGNORE_FILE,
+};
+use crate::scanner::cache::{CacheEntry, ScanCache};
 use crate::scanner::detect_sources::DetectSources;
-use bstr::ByteSlice;
+use crate::scanner::watcher::WatchHandle;
 use fxhash::{FxHashMap, FxHashSet};
 use glob::fast_glob;
 use glob::get_fast_patterns;
+use glob::GlobSet;
 use rayon::prelude::*;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync;
 use std::time::SystemTime;
 use tracing::event;
+use xxhash_rust::xxh3::xxh3_64;
 
+pub mod candidate;
 pub mod cursor;
 pub mod fast_skip;
 pub mod glob;
 pub mod parser;
+pub mod preprocessors;
 pub mod scanner;
+pub mod utf16;
 
 static SHOULD_TRACE: sync::LazyLock<bool> = sync::LazyLock::new(
     || matches!(std::env::var("DEBUG"), Ok(value) if value.eq("*") || value.eq("1") || value.eq("true") || value.contains("tailwind")),
@@ -37,6 +48,9 @@ fn init_tracing() {
 pub struct ChangedContent {
     pub file: Option<PathBuf>,
     pub content: Option<String>,
+    /// File extension (without the leading `.`) used to pick a pre-processor. When empty, the
+    /// extension of `file` is used instead.
+    pub extension: String,
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +66,308 @@ pub struct ScanResult {
     pub candidates: Vec<String>,
     pub files: Vec<String>,
     pub globs: Vec<GlobEntry>,
+    pub diagnostics: Vec<ScanError>,
+}
+
+/// Detect sources, resolve globs and extract all candidates in a single call. Nothing is kept
+/// around, use a `Scanner` when files have to be scanned again later.
+pub fn scan(options: ScanOptions) -> ScanResult {
+    let mut scanner = Scanner::new(
+        options.base.map(|base| DetectSources::new(base.into())),
+        Some(options.sources).filter(|sources| !sources.is_empty()),
+    );
+
+    let candidates = scanner.scan();
+
+    ScanResult {
+        candidates,
+        files: scanner.get_files(),
+        globs: scanner.get_globs(),
+        diagnostics: scanner.diagnostics(),
+    }
+}
+
+/// A problem that was found while scanning. Problems with individual files or sources don't stop
+/// the scan, they are collected in `Scanner::diagnostics` instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    /// A file could not be read
+    ReadFile { file: PathBuf, message: String },
+
+    /// A glob pattern is invalid
+    InvalidGlob { base: PathBuf, message: String },
+
+    /// A base path does not exist or is not a directory
+    MissingBase { base: PathBuf, message: String },
+
+    /// The sources could not be watched
+    Watch { message: String },
+
+    /// A file was skipped because it is larger than `FileLimits::max_file_size`
+    FileTooLarge {
+        file: PathBuf,
+        size: u64,
+        max_size: u64,
+    },
+
+    /// A file was skipped because it looks minified, see `FileLimits::max_average_line_length`
+    MinifiedFile {
+        file: PathBuf,
+        average_line_length: usize,
+        max_average_line_length: usize,
+    },
+}
+
+impl ScanError {
+    /// The file or directory the problem is about, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ScanError::ReadFile { file, .. } => Some(file),
+            ScanError::InvalidGlob { base, .. } => Some(base),
+            ScanError::MissingBase { base, .. } => Some(base),
+            ScanError::Watch { .. } => None,
+            ScanError::FileTooLarge { file, .. } => Some(file),
+            ScanError::MinifiedFile { file, .. } => Some(file),
+        }
+    }
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanError::ReadFile { file, message } => {
+                write!(f, "Failed to read file {}: {}", file.display(), message)
+            }
+            ScanError::InvalidGlob { base, message } => {
+                write!(f, "Invalid glob in {}: {}", base.display(), message)
+            }
+            ScanError::MissingBase { base, message } => {
+                write!(
+                    f,
+                    "Failed to resolve base path {}: {}",
+                    base.display(),
+                    message
+                )
+            }
+            ScanError::Watch { message } => write!(f, "Failed to watch files: {}", message),
+            ScanError::FileTooLarge {
+                file,
+                size,
+                max_size,
+            } => write!(
+                f,
+                "Skipped file {}: {} bytes exceeds the maximum file size of {} bytes",
+                file.display(),
+                size,
+                max_size
+            ),
+            ScanError::MinifiedFile {
+                file,
+                average_line_length,
+                max_average_line_length,
+            } => write!(
+                f,
+                "Skipped minified file {}: average line length of {} exceeds the maximum of {}",
+                file.display(),
+                average_line_length,
+                max_average_line_length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+/// Candidates that were added to or removed from the set of candidates of a `Scanner`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CandidateChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl CandidateChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Combine with changes that happened afterwards. A candidate that was removed and added
+    /// again (or the other way around) is not a change.
+    fn merge(&mut self, other: CandidateChanges) {
+        for candidate in other.added {
+            match self.removed.iter().position(|x| x == &candidate) {
+                Some(idx) => _ = self.removed.remove(idx),
+                None => self.added.push(candidate),
+            }
+        }
+
+        for candidate in other.removed {
+            match self.added.iter().position(|x| x == &candidate) {
+                Some(idx) => _ = self.added.remove(idx),
+                None => self.removed.push(candidate),
+            }
+        }
+
+        self.added.sort();
+        self.removed.sort();
+    }
+}
+
+/// Files that were added to or removed from the files of a `Scanner`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileChanges {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+
+    /// The candidates that are no longer used, because they were only used by removed files. New
+    /// files are not scanned yet, so no candidates are added.
+    pub candidates: CandidateChanges,
+}
+
+impl FileChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.candidates.is_empty()
+    }
+}
+
+/// How the `Scanner` decides whether a file changed since it was last scanned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangeDetection {
+    /// Re-scan files when their modification time changed
+    #[default]
+    Mtime,
+
+    /// Re-scan files when their size or modification time changed
+    SizeAndMtime,
+
+    /// Read and hash every file on each scan, and only extract candidates from files whose
+    /// content changed. Works when modification times can't be trusted, e.g.: files restored
+    /// from a build cache or filesystems with coarse timestamps.
+    ContentHash,
+}
+
+/// Files that are skipped by the `Scanner`, because they are unlikely to be hand-written sources,
+/// e.g.: bundles and source maps. Skipped files are reported in `Scanner::diagnostics`. Content
+/// that is passed in directly is never skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileLimits {
+    /// Skip files that are larger than this many bytes
+    pub max_file_size: Option<u64>,
+
+    /// Skip files where the average line is longer than this many bytes, which is a good sign
+    /// that the file is minified
+    pub max_average_line_length: Option<usize>,
+}
+
+impl FileLimits {
+    /// Why the file with the given size should be skipped, if it should be
+    fn check_size(&self, file: &Path, size: u64) -> Option<ScanError> {
+        let max_size = self.max_file_size?;
+
+        (size > max_size).then(|| ScanError::FileTooLarge {
+            file: file.to_path_buf(),
+            size,
+            max_size,
+        })
+    }
+
+    /// Why the file with the given content should be skipped, if it should be
+    fn check_content(&self, file: &Path, content: &[u8]) -> Option<ScanError> {
+        let max_average_line_length = self.max_average_line_length?;
+
+        let lines = content.iter().filter(|b| **b == b'\n').count() + 1;
+        let average_line_length = content.len() / lines;
+
+        (average_line_length > max_average_line_length).then(|| ScanError::MinifiedFile {
+            file: file.to_path_buf(),
+            average_line_length,
+            max_average_line_length,
+        })
+    }
+}
+
+/// How files are read before their candidates are extracted
+#[derive(Debug, Clone, Copy, Default)]
+struct ReadOptions {
+    /// Skip files that look binary
+    sniff_content: bool,
+
+    limits: FileLimits,
+}
+
+/// Whether a file changed since it was last scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileState {
+    Unchanged,
+    Changed,
+
+    /// The file has to be read to compare its content hash
+    Unknown,
+}
+
+impl ChangeDetection {
+    fn file_state(self, previous: &Fingerprint, current: &Fingerprint) -> FileState {
+        // Without a hash we have nothing to compare the content with
+        let unknown = match previous.hash {
+            Some(_) => FileState::Unknown,
+            None => FileState::Changed,
+        };
+
+        let (Some(mtime), Some(size)) = (current.mtime, current.size) else {
+            // The metadata of the file can't be read
+            return unknown;
+        };
+
+        let same_mtime = previous.mtime == Some(mtime);
+        let same_size = previous.size == Some(size);
+
+        match self {
+            ChangeDetection::Mtime if same_mtime => FileState::Unchanged,
+            ChangeDetection::SizeAndMtime if same_mtime && same_size => FileState::Unchanged,
+            ChangeDetection::Mtime | ChangeDetection::SizeAndMtime => FileState::Changed,
+            ChangeDetection::ContentHash => unknown,
+        }
+    }
+}
+
+/// The state of a file when it was last scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    /// `None` when the metadata of the file can't be read
+    mtime: Option<SystemTime>,
+    size: Option<u64>,
+
+    /// Hash of the scanned content. Only known when the candidates of the file match the content
+    /// on disk, e.g.: not when the file was scanned with in-memory content.
+    hash: Option<u64>,
+}
+
+/// The candidates found in a single piece of content
+#[derive(Debug, Clone)]
+struct ParsedContent {
+    file: Option<PathBuf>,
+
+    /// `None` when the file could not be read, or when the file should be forgotten
+    candidates: Option<Vec<String>>,
+
+    /// Why the file could not be read
+    error: Option<ScanError>,
+
+    hash: u64,
+}
+
+/// The files, compiled globs and ignore files of a `Scanner`, to check whether a path is a source
+#[derive(Debug, Clone)]
+struct SourceMatcher {
+    files: FxHashSet<PathBuf>,
+
+    /// The configured sources, their files are scanned even when they are ignored
+    sources: GlobSet,
+
+    /// All globs, including the globs of detected sources
+    globs: GlobSet,
+
+    /// The ignore files of the directories that were walked to detect sources
+    ignore_matcher: Option<IgnoreMatcher>,
 }
 
 #[derive(Debug, Clone)]
@@ -78,27 +394,90 @@ pub struct Scanner {
     /// All generated globs
     globs: Vec<GlobEntry>,
 
-    /// Track file modification times
-    mtimes: FxHashMap<PathBuf, SystemTime>,
+    /// Compiled `files` and `globs`, built when needed
+    matcher: Option<SourceMatcher>,
+
+    /// All directories that were walked to detect sources, sorted
+    detected_dirs: Vec<PathBuf>,
+
+    /// The ignore files of the `detected_dirs`, read once per walk
+    ignore_matcher: Option<IgnoreMatcher>,
+
+    /// The directories that are watched by the `Watcher` of this scanner, if any
+    watch_handle: WatchHandle,
+
+    /// How to decide whether a file changed since it was last scanned
+    change_detection: ChangeDetection,
+
+    /// Files that are too large or minified are skipped
+    file_limits: FileLimits,
+
+    /// Track the state of each file when it was last scanned
+    fingerprints: FxHashMap<PathBuf, Fingerprint>,
+
+    /// Location of the persistent scan cache, if enabled
+    cache_file: Option<PathBuf>,
+
+    /// Scan results of a previous run, loaded from `cache_file`
+    cache: Option<ScanCache>,
+
+    /// Whether files were scanned since the cache file was last written
+    cache_dirty: bool,
 
     /// Track unique set of candidates
     candidates: FxHashSet<String>,
+
+    /// Track which candidates are used by each file
+    candidates_by_file: FxHashMap<PathBuf, FxHashSet<String>>,
+
+    /// Track which files use each candidate
+    files_by_candidate: FxHashMap<String, FxHashSet<PathBuf>>,
+
+    /// Candidates found in content without a file. We can't know when they are no longer used,
+    /// so they are never removed.
+    untracked_candidates: FxHashSet<String>,
+
+    /// Problems with the configured sources, e.g.: invalid globs
+    source_errors: Vec<ScanError>,
+
+    /// Files that could not be read when they were last scanned
+    file_errors: FxHashMap<PathBuf, ScanError>,
 }
 
 impl Scanner {
     pub fn new(detect_sources: Option<DetectSources>, sources: Option<Vec<GlobEntry>>) -> Self {
-        Self {
-            detect_sources,
-            sources,
-            ..Default::default()
-        }
+        // The scanner writes its cache when dropped, so we can't use `..Default::default()`
+        let mut scanner = Self::default();
+        scanner.detect_sources = detect_sources;
+        scanner.sources = sources;
+        scanner
+    }
+
+    /// Persist scan results in the given cache file, so that files that didn't change since a
+    /// previous run don't have to be read again. The cache file is written by `flush_cache`, and
+    /// when the scanner is dropped.
+    pub fn with_cache_file(mut self, cache_file: PathBuf) -> Self {
+        self.cache_file = Some(cache_file);
+        self
+    }
+
+    /// Use the given strategy to decide whether a file changed since it was last scanned.
+    pub fn with_change_detection(mut self, change_detection: ChangeDetection) -> Self {
+        self.change_detection = change_detection;
+        self
+    }
+
+    /// Skip files that exceed the given limits, e.g.: bundles that ended up in a source directory.
+    pub fn with_file_limits(mut self, file_limits: FileLimits) -> Self {
+        self.file_limits = file_limits;
+        self
     }
 
     pub fn scan(&mut self) -> Vec<String> {
         init_tracing();
         self.prepare();
 
-        self.compute_candidates();
+        self.compute_candidates(&FxHashSet::default());
 
         let mut candidates: Vec<String> = self.candidates.clone().into_iter().collect();
 
@@ -107,31 +486,126 @@ impl Scanner {
         candidates
     }
 
+    /// Same as `scan`, but every candidate is split into its structural parts. Candidates that
+    /// can't be parsed are dropped.
+    pub fn scan_parsed(&mut self) -> Vec<Candidate> {
+        self.scan()
+            .iter()
+            .filter_map(|candidate| Candidate::parse(candidate))
+            .collect()
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn scan_content(&mut self, changed_content: Vec<ChangedContent>) -> Vec<String> {
+        self.scan_content_with_changes(changed_content).added
+    }
+
+    /// Scan the changed content and return the candidates that were added, as well as the
+    /// candidates that are no longer used by any file. Files that can't be read anymore are
+    /// treated as removed.
+    #[tracing::instrument(skip_all)]
+    pub fn scan_content_with_changes(
+        &mut self,
+        changed_content: Vec<ChangedContent>,
+    ) -> CandidateChanges {
         self.prepare();
-        let candidates = parse_all_blobs(read_all_files(changed_content));
 
-        let mut new_candidates = vec![];
-        for candidate in candidates {
-            if self.candidates.contains(&candidate) {
-                continue;
+        // The candidates of these files no longer match what is stored in the cache
+        for file in changed_content.iter().filter_map(|c| c.file.as_ref()) {
+            if let Some(fingerprint) = self.fingerprints.get_mut(file) {
+                fingerprint.hash = None;
             }
-            self.candidates.insert(candidate.clone());
-            new_candidates.push(candidate);
         }
 
-        new_candidates
+        self.track_candidates(parse_all_files(
+            changed_content.into_iter().map(|c| (c, None)).collect(),
+            self.read_options(),
+        ))
     }
 
+    /// Detect sources and resolve globs again, e.g.: to pick up files that were created since the
+    /// last scan. Files that are no longer sources are forgotten, together with the candidates
+    /// that only they used. New files are scanned by the next `scan`. Everything that is known
+    /// about the other files is kept, so they are only scanned again when they changed.
+    #[tracing::instrument(skip_all)]
+    pub fn refresh(&mut self) -> FileChanges {
+        // All files are new when the sources were never resolved
+        if !self.ready {
+            self.prepare();
+
+            let mut added = self.files.clone();
+            added.sort();
+
+            return FileChanges {
+                added,
+                ..Default::default()
+            };
+        }
+
+        self.refresh_sources()
+    }
+
+    /// Replace the glob sources, e.g.: because the `@source` directives changed. Files that are
+    /// still sources keep their candidates, only new files are scanned by the next `scan`. A
+    /// running `Watcher` watches the directories of the new sources from now on.
+    pub fn set_sources(&mut self, sources: Option<Vec<GlobEntry>>) -> FileChanges {
+        self.sources = sources;
+        self.refresh()
+    }
+
+    /// Same as `set_sources`, but replaces the automatic source detection.
+    pub fn set_detect_sources(&mut self, detect_sources: Option<DetectSources>) -> FileChanges {
+        self.detect_sources = detect_sources;
+
+        // The ignore rules are part of the cache options
+        let options = self.cache_options();
+        if let Some(cache) = &mut self.cache {
+            cache.set_options(options);
+        }
+
+        self.refresh()
+    }
+
+    /// Forget about the given files, e.g.: because they were deleted. Returns the candidates that
+    /// are no longer used by any file.
+    #[tracing::instrument(skip_all)]
+    pub fn remove_files(&mut self, files: Vec<PathBuf>) -> CandidateChanges {
+        self.prepare();
+
+        let files: FxHashSet<PathBuf> = files.into_iter().collect();
+        self.files.retain(|file| !files.contains(file));
+        self.matcher = None;
+        self.fingerprints.retain(|file, _| !files.contains(file));
+
+        self.track_candidates(
+            files
+                .into_iter()
+                .map(|file| ParsedContent {
+                    file: Some(file),
+                    candidates: None,
+                    error: None,
+                    hash: 0,
+                })
+                .collect(),
+        )
+    }
+
+    /// All candidates in the content, with the byte offset where they start. Fails when the file
+    /// can't be read.
     #[tracing::instrument(skip_all)]
     pub fn get_candidates_with_positions(
         &mut self,
         changed_content: ChangedContent,
-    ) -> Vec<(String, usize)> {
+    ) -> Result<Vec<(String, usize)>, ScanError> {
         self.prepare();
 
-        let content = read_changed_content(changed_content).unwrap_or_default();
+        // The file is explicitly requested, so it is never skipped because of the limits
+        let options = ReadOptions {
+            limits: FileLimits::default(),
+            ..self.read_options()
+        };
+
+        let content = read_changed_content(changed_content, options)?;
         let extractor = Extractor::with_positions(&content[..], Default::default());
 
         let candidates: Vec<(String, usize)> = extractor
@@ -143,7 +617,65 @@ impl Scanner {
                 unsafe { (String::from_utf8_unchecked(s.to_vec()), i) }
             })
             .collect();
-        candidates
+
+        Ok(candidates)
+    }
+
+    /// Whether the file is a source: it is one of the files, it matches the configured sources, or
+    /// it matches the detected globs and is not ignored. Paths are compared as-is, so they should
+    /// be absolute. Useful to filter file system events. Only the files, globs and ignore files
+    /// that were read when the sources were resolved are used, so the file system is not touched.
+    pub fn matches(&mut self, path: &Path) -> bool {
+        let matcher = self.matcher();
+
+        if matcher.files.contains(path) || matcher.sources.is_match(path) {
+            return true;
+        }
+
+        if !matcher.globs.is_match(path) {
+            return false;
+        }
+
+        // The globs of detected sources match ignored files as well
+        matcher
+            .ignore_matcher
+            .as_ref()
+            .is_some_and(|ignore_matcher| {
+                ignore_matcher.is_walked_path(path, ignore_matcher.is_walked_dir(path))
+            })
+    }
+
+    /// Only keep the paths that are sources, see `matches`.
+    pub fn filter(&mut self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        paths
+            .into_iter()
+            .filter(|path| self.matches(path))
+            .collect()
+    }
+
+    /// The compiled files and globs, built when needed
+    fn matcher(&mut self) -> &SourceMatcher {
+        self.prepare();
+
+        self.matcher.get_or_insert_with(|| SourceMatcher {
+            files: self.files.iter().cloned().collect(),
+            sources: GlobSet::new(
+                &self
+                    .sources
+                    .iter()
+                    .flatten()
+                    .map(|source| GlobEntry {
+                        // The same base as the resolved globs
+                        base: dunce::canonicalize(&source.base)
+                            .map(|base| base.display().to_string())
+                            .unwrap_or_else(|_| source.base.clone()),
+                        pattern: source.pattern.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            globs: GlobSet::new(&self.globs),
+            ignore_matcher: self.ignore_matcher.clone(),
+        })
     }
 
     #[tracing::instrument(skip_all)]
@@ -163,40 +695,413 @@ impl Scanner {
         self.globs.clone()
     }
 
+    /// Problems that were found while resolving the sources and reading files. Problems with
+    /// files are cleared once the file can be read again.
+    pub fn diagnostics(&self) -> Vec<ScanError> {
+        let mut file_errors: Vec<&ScanError> = self.file_errors.values().collect();
+        file_errors.sort_by(|a, z| a.path().cmp(&z.path()));
+
+        self.source_errors
+            .iter()
+            .chain(file_errors)
+            .cloned()
+            .collect()
+    }
+
+    /// All files that used the candidate when they were last scanned.
+    pub fn get_files_for_candidate(&self, candidate: &str) -> Vec<String> {
+        let mut files: Vec<String> = self
+            .files_by_candidate
+            .get(candidate)
+            .map(|files| files.iter().map(|x| x.to_string_lossy().into()).collect())
+            .unwrap_or_default();
+
+        files.sort();
+
+        files
+    }
+
+    /// All candidates the file used when it was last scanned.
+    pub fn get_candidates_for_file(&self, file: &Path) -> Vec<String> {
+        let mut candidates: Vec<String> = self
+            .candidates_by_file
+            .get(file)
+            .map(|candidates| candidates.iter().cloned().collect())
+            .unwrap_or_default();
+
+        candidates.sort();
+
+        candidates
+    }
+
+    /// Re-scan the given paths, e.g.: after the file system reported that they changed. When one
+    /// of the paths could be a new source file, the sources are detected again.
     #[tracing::instrument(skip_all)]
-    fn compute_candidates(&mut self) {
+    fn scan_changed_paths(&mut self, paths: Vec<PathBuf>) -> CandidateChanges {
+        self.prepare();
+
+        let previous_files: FxHashSet<PathBuf> = self.files.iter().cloned().collect();
+        let mut changes = CandidateChanges::default();
+
+        let needs_detection = paths
+            .iter()
+            .filter(|path| !previous_files.contains(*path))
+            .any(|path| self.could_be_source(path));
+
+        if needs_detection {
+            changes.merge(self.refresh_sources().candidates);
+        }
+
+        changes.merge(self.compute_candidates(&paths.into_iter().collect()));
+        changes
+    }
+
+    /// Resolve the sources again, and forget about the files that are no longer sources. Returns
+    /// the files that changed, and the candidates that are no longer used.
+    fn refresh_sources(&mut self) -> FileChanges {
+        let previous_files: FxHashSet<PathBuf> = self.files.iter().cloned().collect();
+
+        self.resolve_sources();
+
+        // The walked directories and the bases of the sources can change
+        if let Err(err) = self.sync_watched_dirs() {
+            self.source_errors.push(err);
+        }
+
+        let current_files: FxHashSet<&PathBuf> = self.files.iter().collect();
+        let mut file_changes = FileChanges {
+            added: current_files
+                .iter()
+                .filter(|file| !previous_files.contains(**file))
+                .map(|file| (*file).clone())
+                .collect(),
+
+            // Files that are no longer allowed, e.g.: because they were deleted or are git
+            // ignored now
+            removed: previous_files
+                .iter()
+                .filter(|file| !current_files.contains(file))
+                .cloned()
+                .collect(),
+
+            candidates: CandidateChanges::default(),
+        };
+
+        file_changes.added.sort();
+        file_changes.removed.sort();
+
+        if !file_changes.removed.is_empty() {
+            file_changes.candidates = self.remove_files(file_changes.removed.clone());
+        }
+
+        file_changes
+    }
+
+    fn ignore_rules(&self) -> &IgnoreRules {
+        match &self.detect_sources {
+            Some(detect_sources) => detect_sources.ignore_rules(),
+            None => &DEFAULT_IGNORE_RULES,
+        }
+    }
+
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            sniff_content: self.ignore_rules().sniff_content(),
+            limits: self.file_limits,
+        }
+    }
+
+    /// Whether a new path could contain sources, or change which files are sources. Paths that
+    /// are ignored, e.g.: by a `.gitignore` file, can't.
+    fn could_be_source(&mut self, path: &Path) -> bool {
+        let matcher = self.matcher();
+
+        // Changes inside of ignored directories, e.g.: `.git`, can't be sources
+        if matcher
+            .ignore_matcher
+            .as_ref()
+            .is_some_and(|ignore_matcher| ignore_matcher.is_inside_ignored_dir(path))
+        {
+            return false;
+        }
+
+        // Ignore files change which files are allowed
+        if path
+            .file_name()
+            .is_some_and(|name| name == ".gitignore" || name == TAILWIND_IGNORE_FILE)
+        {
+            return true;
+        }
+
+        // Configured sources are scanned even when they are ignored
+        if matcher.sources.is_match(path) {
+            return true;
+        }
+
+        // Only paths in directories that were walked can be detected
+        matcher
+            .ignore_matcher
+            .as_ref()
+            .is_some_and(|ignore_matcher| {
+                path.parent()
+                    .is_some_and(|parent| ignore_matcher.is_walked_dir(parent))
+                    && ignore_matcher.is_walked_path(path, path.is_dir())
+            })
+    }
+
+    /// Scan all files that changed since they were last scanned. The `changed_files` are known to
+    /// be changed, regardless of the change detection strategy.
+    #[tracing::instrument(skip_all)]
+    fn compute_candidates(&mut self, changed_files: &FxHashSet<PathBuf>) -> CandidateChanges {
+        let mut changes = CandidateChanges::default();
         let mut changed_content = vec![];
+        let mut cached_content = vec![];
+        let mut removed_files = vec![];
+        let mut has_changes = false;
 
         for path in &self.files {
-            let current_time = fs::metadata(path)
-                .and_then(|m| m.modified())
-                .unwrap_or(SystemTime::now());
+            let metadata = fs::metadata(path);
+
+            // The file was deleted, so we have to forget about its candidates
+            if matches!(&metadata, Err(e) if e.kind() == std::io::ErrorKind::NotFound) {
+                removed_files.push(path.clone());
+                continue;
+            }
 
-            let previous_time = self.mtimes.insert(path.clone(), current_time);
+            let metadata = metadata.ok();
+            let mut current = Fingerprint {
+                mtime: metadata.as_ref().and_then(|m| m.modified().ok()),
+                size: metadata.as_ref().map(|m| m.len()),
+                hash: None,
+            };
+
+            // Files we didn't see before might have been scanned by a previous run
+            let cached = match self.fingerprints.contains_key(path) {
+                true => None,
+                false => self.cache.as_ref().and_then(|cache| cache.get(path)),
+            };
+
+            let previous = self.fingerprints.get(path).copied().or_else(|| {
+                cached.map(|entry| Fingerprint {
+                    mtime: Some(entry.modified()),
+                    size: Some(entry.size),
+                    hash: Some(entry.hash),
+                })
+            });
 
-            let should_scan_file = match previous_time {
-                // Time has changed, so we need to re-scan the file
-                Some(prev) if prev != current_time => true,
+            let state = match &previous {
+                // We only have to compare the content when we know it was scanned before
+                Some(previous) if changed_files.contains(path) => match previous.hash {
+                    Some(_) => FileState::Unknown,
+                    None => FileState::Changed,
+                },
 
-                // File was in the cache, no need to re-scan
-                Some(_) => false,
+                Some(previous) => self.change_detection.file_state(previous, &current),
 
                 // File didn't exist before, so we need to scan it
-                None => true,
+                None => FileState::Changed,
             };
 
-            if should_scan_file {
-                changed_content.push(ChangedContent {
-                    file: Some(path.clone()),
-                    content: None,
-                });
+            let previous_hash = previous.and_then(|previous| previous.hash);
+
+            if state != FileState::Changed {
+                // Keep the hash, so that the cache stays valid. When the content turns out to be
+                // changed, the hash is replaced after parsing.
+                current.hash = previous_hash;
+
+                // Start with the cached candidates, they are replaced if the content changed
+                if let Some(entry) = cached {
+                    cached_content.push(ParsedContent {
+                        file: Some(path.clone()),
+                        candidates: Some(entry.candidates.clone()),
+                        error: None,
+                        hash: entry.hash,
+                    });
+                }
+            }
+
+            if state != FileState::Unchanged {
+                changed_content.push((
+                    ChangedContent {
+                        file: Some(path.clone()),
+                        content: None,
+                        extension: String::new(),
+                    },
+                    match state {
+                        FileState::Unknown => previous_hash,
+                        _ => None,
+                    },
+                ));
+            }
+
+            if self.fingerprints.insert(path.clone(), current) != Some(current) {
+                has_changes = true;
             }
         }
 
+        if !removed_files.is_empty() {
+            has_changes = true;
+            changes.merge(self.remove_files(removed_files));
+        }
+
+        if !cached_content.is_empty() {
+            event!(
+                tracing::Level::INFO,
+                "Restored {:?} file(s) from the cache",
+                cached_content.len()
+            );
+            changes.merge(self.track_candidates(cached_content));
+        }
+
         if !changed_content.is_empty() {
-            let candidates = parse_all_blobs(read_all_files(changed_content));
-            self.candidates.extend(candidates);
+            let parsed = parse_all_files(changed_content, self.read_options());
+
+            for content in &parsed {
+                if let Some(fingerprint) = content
+                    .file
+                    .as_ref()
+                    .and_then(|file| self.fingerprints.get_mut(file))
+                {
+                    fingerprint.hash = content.candidates.as_ref().map(|_| content.hash);
+                    has_changes = true;
+                }
+            }
+
+            changes.merge(self.track_candidates(parsed));
+        }
+
+        if has_changes {
+            self.cache_dirty = true;
         }
+
+        changes
+    }
+
+    /// Write the candidates of all files that match their content on disk to the cache file, when
+    /// files were scanned since it was last written.
+    pub fn flush_cache(&mut self) {
+        if !self.cache_dirty {
+            return;
+        }
+
+        let Some(cache) = &mut self.cache else {
+            return;
+        };
+
+        let entries = self.fingerprints.iter().filter_map(|(file, fingerprint)| {
+            let hash = fingerprint.hash?;
+            let candidates = self.candidates_by_file.get(file)?;
+
+            Some((
+                file.as_path(),
+                CacheEntry::new(
+                    fingerprint.size?,
+                    fingerprint.mtime?,
+                    hash,
+                    candidates.iter().cloned().collect(),
+                ),
+            ))
+        });
+
+        cache.write(entries);
+        self.cache_dirty = false;
+    }
+
+    /// Everything that changes which candidates are found in a file. A cache that was written with
+    /// other options can't be used.
+    fn cache_options(&self) -> String {
+        format!(
+            "{:?} {:?} {}",
+            ExtractorOptions::default(),
+            self.read_options(),
+            self.ignore_rules().cache_key()
+        )
+    }
+
+    /// Register the candidates that were found in each file and update the indexes. A file that
+    /// is scanned again replaces the candidates it used before, a file without candidates (`None`)
+    /// is forgotten. Candidates that are no longer used by any file are removed.
+    #[tracing::instrument(skip_all)]
+    fn track_candidates(&mut self, parsed: Vec<ParsedContent>) -> CandidateChanges {
+        // Whether each candidate we touched was known before we started, so that candidates that
+        // move from one file to another are not reported as changed.
+        let mut touched: FxHashMap<String, bool> = FxHashMap::default();
+
+        for ParsedContent {
+            file,
+            candidates,
+            error,
+            ..
+        } in parsed
+        {
+            let Some(file) = file else {
+                for candidate in candidates.unwrap_or_default() {
+                    touched
+                        .entry(candidate.clone())
+                        .or_insert_with(|| self.candidates.contains(&candidate));
+
+                    self.candidates.insert(candidate.clone());
+                    self.untracked_candidates.insert(candidate);
+                }
+                continue;
+            };
+
+            match error {
+                Some(error) => self.file_errors.insert(file.clone(), error),
+                None => self.file_errors.remove(&file),
+            };
+
+            let is_readable = candidates.is_some();
+            let current: FxHashSet<String> = candidates.unwrap_or_default().into_iter().collect();
+            let previous = self.candidates_by_file.remove(&file).unwrap_or_default();
+
+            for candidate in previous.difference(&current) {
+                touched
+                    .entry(candidate.clone())
+                    .or_insert_with(|| self.candidates.contains(candidate));
+
+                if let Some(files) = self.files_by_candidate.get_mut(candidate) {
+                    files.remove(&file);
+
+                    if files.is_empty() {
+                        self.files_by_candidate.remove(candidate);
+
+                        if !self.untracked_candidates.contains(candidate) {
+                            self.candidates.remove(candidate);
+                        }
+                    }
+                }
+            }
+
+            for candidate in current.difference(&previous) {
+                touched
+                    .entry(candidate.clone())
+                    .or_insert_with(|| self.candidates.contains(candidate));
+
+                self.files_by_candidate
+                    .entry(candidate.clone())
+                    .or_default()
+                    .insert(file.clone());
+                self.candidates.insert(candidate.clone());
+            }
+
+            if is_readable {
+                self.candidates_by_file.insert(file, current);
+            }
+        }
+
+        let mut changes = CandidateChanges::default();
+        for (candidate, was_known) in touched {
+            match (was_known, self.candidates.contains(&candidate)) {
+                (false, true) => changes.added.push(candidate),
+                (true, false) => changes.removed.push(candidate),
+                _ => {}
+            }
+        }
+
+        changes.added.sort();
+        changes.removed.sort();
+        changes
     }
 
     // Ensures that all files/globs are resolved and the scanner is ready to scan
@@ -206,18 +1111,47 @@ impl Scanner {
             return;
         }
 
+        if let Some(cache_file) = &self.cache_file {
+            self.cache = Some(ScanCache::load(cache_file.clone(), self.cache_options()));
+        }
+
+        self.resolve_sources();
+
+        self.ready = true;
+    }
+
+    /// Resolve all files and globs from the configured sources
+    fn resolve_sources(&mut self) {
+        self.files.clear();
+        self.globs.clear();
+        self.detected_dirs.clear();
+        self.ignore_matcher = None;
+        self.matcher = None;
+        self.source_errors.clear();
+
         self.detect_sources();
         self.scan_sources();
 
-        self.ready = true;
+        // Files can be detected and matched by a glob at the same time. Both are canonical, so the
+        // same file has the same path.
+        let mut seen = FxHashSet::default();
+        self.files.retain(|file| seen.insert(file.clone()));
     }
 
     #[tracing::instrument(skip_all)]
     fn detect_sources(&mut self) {
         if let Some(detect_sources) = &self.detect_sources {
-            let (files, globs) = detect_sources.detect();
-            self.files.extend(files);
-            self.globs.extend(globs);
+            let detected = detect_sources.detect_all();
+
+            for err in &detected.errors {
+                event!(tracing::Level::ERROR, "{}", err);
+            }
+
+            self.files.extend(detected.files);
+            self.globs.extend(detected.globs);
+            self.detected_dirs = detected.dirs;
+            self.ignore_matcher = detected.ignore_matcher;
+            self.source_errors.extend(detected.errors);
         }
     }
 
@@ -231,24 +1165,25 @@ impl Scanner {
             return;
         }
 
-        let resolved_files: Vec<_> = match fast_glob(sources) {
-            Ok(matches) => matches
-                .filter_map(|x| dunce::canonicalize(&x).ok())
-                .collect(),
-            Err(err) => {
-                event!(tracing::Level::ERROR, "Failed to resolve glob: {:?}", err);
-                vec![]
-            }
-        };
+        let (matches, errors) = fast_glob(sources);
+        let resolved_files: Vec<_> = matches
+            .filter_map(|x| dunce::canonicalize(&x).ok())
+            .collect();
+
+        for err in errors {
+            event!(tracing::Level::ERROR, "Failed to resolve glob: {:?}", err);
+            self.source_errors.push(err);
+        }
 
         self.files.extend(resolved_files);
         self.globs.extend(sources.clone());
 
         // Re-optimize the globs to reduce the number of patterns we have to scan.
+        let mut source_errors = vec![];
         self.globs = get_fast_patterns(&self.globs)
             .into_iter()
             .filter_map(|(root, globs)| {
-                let root = match dunce::canonicalize(root) {
+                let root = match dunce::canonicalize(&root) {
                     Ok(root) => root,
                     Err(error) => {
                         event!(
@@ -256,6 +1191,10 @@ impl Scanner {
                             "Failed to canonicalize base path {:?}",
                             error
                         );
+                        source_errors.push(ScanError::MissingBase {
+                            base: root,
+                            message: error.to_string(),
+                        });
                         return None;
                     }
                 };
@@ -271,37 +1210,74 @@ impl Scanner {
                 })
             })
             .collect::<Vec<GlobEntry>>();
+
+        self.source_errors.extend(source_errors);
     }
 }
 
-fn read_changed_content(c: ChangedContent) -> Option<Vec<u8>> {
+impl Drop for Scanner {
+    fn drop(&mut self) {
+        self.flush_cache();
+    }
+}
+
+/// Read the content, and pre-process it based on the extension. Files that look binary (when
+/// content sniffing is enabled) are skipped, files that exceed the limits result in an error.
+fn read_changed_content(c: ChangedContent, options: ReadOptions) -> Result<Vec<u8>, ScanError> {
+    let extension = match (c.extension.is_empty(), &c.file) {
+        (true, Some(file)) => file
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        _ => c.extension,
+    };
+
     if let Some(content) = c.content {
-        return Some(content.into_bytes());
+        return Ok(pre_process_input(content.into_bytes(), &extension));
     }
 
     let Some(file) = c.file else {
-        return Default::default();
+        return Ok(Default::default());
     };
 
-    let Ok(content) = std::fs::read(&file).map_err(|e| {
+    // Large files are not read at all, reading them is what we want to avoid
+    if let Some(error) = fs::metadata(&file)
+        .ok()
+        .and_then(|metadata| options.limits.check_size(&file, metadata.len()))
+    {
+        event!(tracing::Level::INFO, "{}", error);
+        return Err(error);
+    }
+
+    let content = std::fs::read(&file).map_err(|e| {
         event!(tracing::Level::ERROR, "Failed to read file: {:?}", e);
-        e
-    }) else {
-        return Default::default();
-    };
+        ScanError::ReadFile {
+            file: file.clone(),
+            message: e.to_string(),
+        }
+    })?;
 
-    let Some(extension) = file.extension().map(|x| x.to_str()) else {
-        return Some(content);
-    };
+    if options.sniff_content && is_binary(&content) {
+        event!(tracing::Level::INFO, "Skipping binary file: {:?}", file);
+        return Ok(Default::default());
+    }
 
-    match extension {
-        Some("svelte") => Some(content.replace(" class:", " ")),
-        _ => Some(content),
+    if let Some(error) = options.limits.check_content(&file, &content) {
+        event!(tracing::Level::INFO, "{}", error);
+        return Err(error);
     }
+
+    Ok(pre_process_input(content, &extension))
 }
 
+/// Read and parse all changed content. Content is skipped when its hash is the same as the given
+/// hash of the previously scanned content.
 #[tracing::instrument(skip_all)]
-fn read_all_files(changed_content: Vec<ChangedContent>) -> Vec<Vec<u8>> {
+fn parse_all_files(
+    changed_content: Vec<(ChangedContent, Option<u64>)>,
+    options: ReadOptions,
+) -> Vec<ParsedContent> {
     event!(
         tracing::Level::INFO,
         "Reading {:?} file(s)",
@@ -310,30 +1286,43 @@ fn read_all_files(changed_content: Vec<ChangedContent>) -> Vec<Vec<u8>> {
 
     changed_content
         .into_par_iter()
-        .filter_map(read_changed_content)
-        .collect()
-}
+        .filter_map(|(c, previous_hash)| {
+            let file = c.file.clone();
 
-#[tracing::instrument(skip_all)]
-fn parse_all_blobs(blobs: Vec<Vec<u8>>) -> Vec<String> {
-    let input: Vec<_> = blobs.iter().map(|blob| &blob[..]).collect();
-    let input = &input[..];
-
-    let mut result: Vec<String> = input
-        .par_iter()
-        .map(|input| Extractor::unique(input, Default::default()))
-        .reduce(Default::default, |mut a, b| {
-            a.extend(b);
-            a
-        })
-        .into_iter()
-        .map(|s| {
-            // SAFETY: When we parsed the candidates, we already guaranteed that the byte slices
-            // are valid, therefore we don't have to re-check here when we want to convert it back
-            // to a string.
-            unsafe { String::from_utf8_unchecked(s.to_vec()) }
+            // Files that can't be read or are skipped are reported without candidates
+            let content = match read_changed_content(c, options) {
+                Ok(content) => content,
+                Err(error) => {
+                    return file.map(|file| ParsedContent {
+                        file: Some(file),
+                        candidates: None,
+                        error: Some(error),
+                        hash: 0,
+                    })
+                }
+            };
+
+            let hash = xxh3_64(&content);
+            if previous_hash == Some(hash) {
+                return None;
+            }
+
+            let candidates: Vec<String> = Extractor::unique(&content, Default::default())
+                .into_iter()
+                .map(|s| {
+                    // SAFETY: When we parsed the candidates, we already guaranteed that the byte
+                    // slices are valid, therefore we don't have to re-check here when we want to
+                    // convert it back to a string.
+                    unsafe { String::from_utf8_unchecked(s.to_vec()) }
+                })
+                .collect();
+
+            Some(ParsedContent {
+                file,
+                candidates: Some(candidates),
+                error: None,
+                hash,
+            })
         })
-        .collect();
-    result.sort();
-    result
+        .collect()
 }
@@ -34,7 +34,7 @@ pub enum ValidationResult {
     Restart,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct ExtractorOptions {
     pub preserve_spaces_in_arbitrary: bool,
 }
@@ -50,6 +50,9 @@ pub struct Extractor<'a> {
     idx_last: usize,
     idx_arbitrary_start: usize,
 
+    /// The character that ends the current arbitrary value, either `]` or `)`
+    arbitrary_end: u8,
+
     in_arbitrary: bool,
     in_candidate: bool,
     in_escape: bool,
@@ -106,6 +109,7 @@ impl<'a> Extractor<'a> {
             idx_start: 0,
             idx_end: 0,
             idx_arbitrary_start: 0,
+            arbitrary_end: b']',
 
             in_arbitrary: false,
             in_candidate: false,
@@ -193,8 +197,8 @@ impl<'a> Extractor<'a> {
 
         for (n, c) in candidate.iter().enumerate() {
             match c {
-                b'[' => brackets += 1,
-                b']' if brackets > 0 => brackets -= 1,
+                b'[' | b'(' => brackets += 1,
+                b']' | b')' if brackets > 0 => brackets -= 1,
                 b':' if brackets == 0 => idx_end = n + 1,
                 _ => {}
             }
@@ -212,8 +216,8 @@ impl<'a> Extractor<'a> {
 
         for c in candidate {
             match c {
-                b'[' => brackets += 1,
-                b']' if brackets > 0 => brackets -= 1,
+                b'[' | b'(' => brackets += 1,
+                b']' | b')' if brackets > 0 => brackets -= 1,
                 _ if brackets == 0 && bytes.contains(c) => return true,
                 _ => {}
             }
@@ -322,6 +326,11 @@ impl<'a> Extractor<'a> {
         // Pluck out the part that we are interested in.
         let utility = &utility[offset..(utility.len() - offset_end)];
 
+        // Parenthesized arbitrary values are a shorthand for CSS variables, e.g.: `bg-(--brand)`
+        if !Self::validate_css_variable_shorthands(utility) {
+            return ValidationResult::Invalid;
+        }
+
         // Validations
         // We should have _something_
         if utility.is_empty() {
@@ -446,6 +455,71 @@ impl<'a> Extractor<'a> {
         true
     }
 
+    /// Make sure that every parenthesized arbitrary value in the utility (the `(…)` part of
+    /// `bg-(--brand)` or `bg-red-500/(--opacity)`) is balanced and references a CSS variable,
+    /// optionally preceded by a type hint, e.g.: `bg-(color:--brand)`.
+    ///
+    /// `utility` - the utility part of the candidate, without variants
+    fn validate_css_variable_shorthands(utility: &[u8]) -> bool {
+        let mut brackets = 0;
+        let mut idx = 0;
+
+        while idx < utility.len() {
+            match utility[idx] {
+                b'[' => brackets += 1,
+                b']' if brackets > 0 => brackets -= 1,
+                b'(' if brackets == 0 && idx > 0 && matches!(utility[idx - 1], b'-' | b'/') => {
+                    let mut depth = 0;
+                    let Some(len) = utility[idx..].iter().position(|c| {
+                        match c {
+                            b'(' => depth += 1,
+                            b')' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    }) else {
+                        return false;
+                    };
+
+                    if !Self::is_css_variable_shorthand(&utility[idx + 1..idx + len]) {
+                        return false;
+                    }
+
+                    idx += len;
+                }
+                _ => {}
+            }
+
+            idx += 1;
+        }
+
+        true
+    }
+
+    /// Returns the length of the optional type hint and the `--` that start a CSS variable
+    /// shorthand, e.g.: `--brand` or `color:--brand`. Returns `None` if the input doesn't start
+    /// like a CSS variable.
+    #[inline(always)]
+    fn css_variable_shorthand_start(input: &[u8]) -> Option<usize> {
+        let hint = input
+            .iter()
+            .position(|c| !matches!(c, b'a'..=b'z' | b'-'))
+            .filter(|n| *n > 0 && input[0].is_ascii_alphabetic() && input[*n] == b':')
+            .map_or(0, |n| n + 1);
+
+        input[hint..].starts_with(b"--").then_some(hint + 2)
+    }
+
+    /// Whether the value of a CSS variable shorthand (without the surrounding parentheses) is
+    /// valid, e.g.: `--brand` or `color:--brand`. Nested parentheses have to be balanced.
+    fn is_css_variable_shorthand(value: &[u8]) -> bool {
+        match Self::css_variable_shorthand_start(value) {
+            // We need an actual variable name
+            Some(start) => value.len() > start && Self::is_balanced(value),
+            None => false,
+        }
+    }
+
     #[inline(always)]
     fn parse_escaped(&mut self) -> ParseAction<'a> {
         // If this character is escaped, we don't care about it.
@@ -477,6 +551,24 @@ impl<'a> Extractor<'a> {
                     self.bracket_stack.pop();
                 }
 
+                // This is the last parenthesis meaning the end of the CSS variable shorthand
+                _ if !self.in_quotes() && self.arbitrary_end == b')' => {
+                    // The shorthand can't be followed by more characters of the utility itself
+                    if self.cursor.next.is_ascii_alphanumeric()
+                        || matches!(self.cursor.next, b'-' | b'_')
+                    {
+                        return ParseAction::Skip;
+                    }
+
+                    trace!("Arbitrary::End\t");
+                    self.in_arbitrary = false;
+
+                    if self.cursor.pos - self.idx_arbitrary_start == 1 {
+                        // We have an empty arbitrary value, which is not allowed
+                        return ParseAction::Skip;
+                    }
+                }
+
                 // Last bracket is different compared to what we expect, therefore we are not in a
                 // valid arbitrary value.
                 _ if !self.in_quotes() => return ParseAction::Skip,
@@ -493,6 +585,9 @@ impl<'a> Extractor<'a> {
                     self.bracket_stack.pop();
                 }
 
+                // A `]` can't end a CSS variable shorthand
+                _ if !self.in_quotes() && self.arbitrary_end == b')' => return ParseAction::Skip,
+
                 // This is the last bracket meaning the end of arbitrary content
                 _ if !self.in_quotes() => {
                     if matches!(self.cursor.next, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9') {
@@ -552,6 +647,7 @@ impl<'a> Extractor<'a> {
             b'[' => {
                 trace!("Arbitrary::Start\t");
                 self.in_arbitrary = true;
+                self.arbitrary_end = b']';
                 self.idx_arbitrary_start = self.cursor.pos;
 
                 ParseAction::Consume
@@ -590,6 +686,7 @@ impl<'a> Extractor<'a> {
             {
                 trace!("Arbitrary::Start\t");
                 self.in_arbitrary = true;
+                self.arbitrary_end = b']';
                 self.idx_arbitrary_start = self.cursor.pos;
             }
 
@@ -601,6 +698,18 @@ impl<'a> Extractor<'a> {
                 return ParseAction::Skip;
             }
 
+            // Enter arbitrary value mode for CSS variable shorthands, e.g.: `bg-(--brand)` or
+            // `bg-red-500/(--opacity)`
+            b'(' if matches!(self.cursor.prev, b'-' | b'/')
+                && Self::css_variable_shorthand_start(&self.input[self.cursor.pos + 1..])
+                    .is_some() =>
+            {
+                trace!("Arbitrary::Start\t");
+                self.in_arbitrary = true;
+                self.arbitrary_end = b')';
+                self.idx_arbitrary_start = self.cursor.pos;
+            }
+
             // A % can only appear at the end of the candidate itself. It can also only be after a
             // digit 0-9. This covers the following cases:
             // - from-15%
@@ -644,9 +753,9 @@ impl<'a> Extractor<'a> {
             }
 
             // Allowed characters in the candidate itself
-            // None of these can come after a closing bracket `]`
+            // None of these can come after a closing bracket `]` or `)`
             b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'@'
-                if self.cursor.prev != b']' =>
+                if self.cursor.prev != b']' && self.cursor.prev != b')' =>
             {
                 /* TODO: The `b'@'` is necessary for custom separators like _@, maybe we can handle this in a better way... */
                 trace!("Candidate::Consume\t");
@@ -987,6 +1096,90 @@ mod test {
         assert_eq!(candidates, vec!["hover:m-[2px]"]);
     }
 
+    #[test]
+    fn it_can_parse_utilities_with_css_variable_shorthands() {
+        let candidates = run("bg-(--brand)", false);
+        assert_eq!(candidates, vec!["bg-(--brand)"]);
+
+        let candidates = run("w-(--sidebar-width)", false);
+        assert_eq!(candidates, vec!["w-(--sidebar-width)"]);
+
+        let candidates = run("bg-(color:--brand)", false);
+        assert_eq!(candidates, vec!["bg-(color:--brand)"]);
+    }
+
+    #[test]
+    fn it_can_parse_css_variable_shorthands_with_variants_and_modifiers() {
+        let candidates = run("hover:text-(--accent)/50", false);
+        assert_eq!(candidates, vec!["hover:text-(--accent)/50"]);
+
+        let candidates = run("bg-red-500/(--opacity)", false);
+        assert_eq!(candidates, vec!["bg-red-500/(--opacity)"]);
+
+        let candidates = run("bg-(--brand)/[50%]", false);
+        assert_eq!(candidates, vec!["bg-(--brand)/[50%]"]);
+    }
+
+    #[test]
+    fn it_should_keep_important_css_variable_shorthands() {
+        let candidates = run("bg-(--brand)!", false);
+        assert_eq!(candidates, vec!["bg-(--brand)!"]);
+
+        let candidates = run("!bg-(--brand)", false);
+        assert_eq!(candidates, vec!["!bg-(--brand)"]);
+    }
+
+    #[test]
+    fn it_can_parse_css_variable_shorthands_in_html() {
+        let candidates = run(
+            r#"<div class="bg-(--brand) hover:text-(--accent)/50"></div>"#,
+            false,
+        );
+        assert_eq!(
+            candidates,
+            vec!["div", "class", "bg-(--brand)", "hover:text-(--accent)/50"]
+        );
+    }
+
+    #[test]
+    fn it_throws_away_invalid_css_variable_shorthands() {
+        let candidates = run("bg-(--brand", false);
+        assert!(candidates.is_empty());
+
+        let candidates = run("bg-(--brand]", false);
+        assert!(candidates.is_empty());
+
+        let candidates = run("bg-(--)", false);
+        assert!(candidates.is_empty());
+
+        let candidates = run("bg-(--brand)px", false);
+        assert_eq!(candidates, vec!["px"]);
+
+        // Not a CSS variable, so we pick up the inner word instead
+        let candidates = run("foo-(bar)", false);
+        assert_eq!(candidates, vec!["bar"]);
+    }
+
+    #[test]
+    fn it_validates_css_variable_shorthands() {
+        assert_eq!(
+            Extractor::is_valid_candidate_string(b"bg-(--brand)"),
+            ValidationResult::Valid
+        );
+        assert_eq!(
+            Extractor::is_valid_candidate_string(b"md:bg-(color:--brand)/(--alpha)!"),
+            ValidationResult::Valid
+        );
+        assert_eq!(
+            Extractor::is_valid_candidate_string(b"bg-(brand)"),
+            ValidationResult::Invalid
+        );
+        assert_eq!(
+            Extractor::is_valid_candidate_string(b"bg-(--brand"),
+            ValidationResult::Invalid
+        );
+    }
+
     #[test]
     fn it_can_parse_arbitrary_variants() {
         let candidates = run("[@media(min-width:200px)]:underline", false);
@@ -0,0 +1,164 @@
+use crate::cursor::Cursor;
+
+/// Indentation based template languages (Pug, Haml, Slim, ...) allow chaining classes onto an
+/// element with `.`, e.g.: `.flex.items-center` or `%div.hover:underline`. The `Extractor` treats
+/// `.` as a character that breaks a candidate, so we replace every `.` that acts as a class
+/// separator with a space.
+///
+/// ```diff
+/// - %div.flex.items-center.px-2.5
+/// + %div flex items-center px-2.5
+/// ```
+///
+/// Only dots of an element are replaced: the first word of a line (e.g.: `div.flex` or
+/// `%div.flex`), the element after Pug's block expansion (e.g.: `li: a.underline`), or a word that
+/// starts with a `.` (e.g.: `.flex`). Dots in other words are kept, e.g.: in `example.com` or
+/// `user.name`.
+///
+/// Dots inside of brackets are kept because they are part of arbitrary values or attributes, e.g.:
+/// `bg-[url(./hero.png)]`. Dots between digits are kept as well, e.g.: `px-2.5`, unless the digits
+/// after the dot are the start of a variant, e.g.: `.px-2.2xl:px-4`.
+pub fn replace_class_shorthand(content: &[u8]) -> Vec<u8> {
+    let mut result = content.to_vec();
+    let mut cursor = Cursor::new(content);
+    let mut depth = 0usize;
+
+    // Whether the current word is an element with chained classes
+    let mut is_element = false;
+
+    // Whether the next word is an element, which is the case at the start of a line
+    let mut expects_element = true;
+
+    while cursor.pos < content.len() {
+        // A new word starts after whitespace
+        if depth == 0
+            && !cursor.curr.is_ascii_whitespace()
+            && (cursor.at_start || cursor.prev.is_ascii_whitespace())
+        {
+            is_element = expects_element || cursor.curr == b'.';
+            expects_element = false;
+        }
+
+        match cursor.curr {
+            // Attributes rarely span multiple lines, resetting the bracket depth ensures that an
+            // unbalanced bracket in plain text doesn't disable the replacements for the rest of
+            // the file.
+            b'\n' => {
+                depth = 0;
+                is_element = false;
+                expects_element = true;
+            }
+
+            // Pug's block expansion puts another element on the same line, e.g.: `li: a.underline`
+            c if c.is_ascii_whitespace() && depth == 0 => {
+                expects_element = expects_element || (is_element && cursor.prev == b':');
+                is_element = false;
+            }
+
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+
+            b'.' if depth == 0
+                && is_element
+                && is_class_start(cursor.next)
+                && !is_decimal_point(content, cursor.pos) =>
+            {
+                result[cursor.pos] = b' ';
+            }
+
+            _ => {}
+        }
+
+        cursor.advance_by(1);
+    }
+
+    result
+}
+
+/// Whether a class can start with the given character, e.g.: `flex`, `-mt-2` or `!p-4`.
+fn is_class_start(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'!' | b'@' | b'[')
+}
+
+/// Whether the `.` at `pos` is part of a number, e.g.: `px-2.5`, instead of separating two classes
+/// where the second one starts with a variant, e.g.: `px-2.2xl:px-4`.
+pub(crate) fn is_decimal_point(content: &[u8], pos: usize) -> bool {
+    pos > 0
+        && content[pos - 1].is_ascii_digit()
+        && content.get(pos + 1).is_some_and(u8::is_ascii_digit)
+        && !starts_with_variant(&content[pos + 1..])
+}
+
+/// Whether the input starts with something that looks like a variant, e.g.: `2xl:flex`.
+fn starts_with_variant(input: &[u8]) -> bool {
+    for c in input {
+        match c {
+            b':' => return true,
+            c if c.is_ascii_alphanumeric() || *c == b'-' => continue,
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> String {
+        let actual = replace_class_shorthand(input.as_bytes());
+        assert_eq!(actual.len(), input.len());
+        String::from_utf8(actual).unwrap()
+    }
+
+    #[test]
+    fn it_splits_chained_classes() {
+        assert_eq!(run(".flex.items-center.p-4"), " flex items-center p-4");
+        assert_eq!(run("%div.hover:underline"), "%div hover:underline");
+    }
+
+    #[test]
+    fn it_keeps_decimals() {
+        assert_eq!(run("div.px-2.5.py-1.5"), "div px-2.5 py-1.5");
+    }
+
+    #[test]
+    fn it_splits_variants_that_start_with_a_digit() {
+        assert_eq!(run(".px-2.2xl:px-4"), " px-2 2xl:px-4");
+    }
+
+    #[test]
+    fn it_keeps_dots_inside_of_brackets() {
+        assert_eq!(
+            run("a.bg-[url(./hero.png)](href='foo.html').mt-1"),
+            "a bg-[url(./hero.png)](href='foo.html') mt-1"
+        );
+    }
+
+    #[test]
+    fn it_splits_elements_after_block_expansion() {
+        assert_eq!(run("li: a.underline.p-2"), "li: a underline p-2");
+    }
+
+    #[test]
+    fn it_keeps_dots_outside_of_elements() {
+        let input = "Visit example.com or call user.name.first\n%p= user.name.first\n";
+        assert_eq!(run(input), input);
+        assert_eq!(
+            run("a.underline(href='https://example.com') example.com"),
+            "a underline(href='https://example.com') example.com"
+        );
+
+        // Dots that are not followed by a class, e.g.: Pug's block in a tag
+        assert_eq!(run("script.\n  p. Hello"), "script.\n  p. Hello");
+    }
+
+    #[test]
+    fn it_resets_brackets_on_new_lines() {
+        assert_eq!(
+            run("p Hello (world\n.flex.p-4"),
+            "p Hello (world\n flex p-4"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cursor::Cursor;
+use crate::preprocessors::class_shorthand::is_decimal_point;
+use crate::preprocessors::PreProcessor;
+
+/// Hiccup vectors describe elements with keywords where classes are chained onto the tag name,
+/// e.g.: `[:div.flex.gap-2 {:class "p-4"}]`. We blank out the leading `:` of keywords and the `.`
+/// separators inside of them so that each class becomes a standalone candidate.
+///
+/// ```diff
+/// - [:div.flex.gap-2 {:class "p-4"}]
+/// + [ div flex gap-2 { class "p-4"}]
+/// ```
+#[derive(Debug, Default)]
+pub struct Clojure;
+
+impl PreProcessor for Clojure {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        let mut result = content.to_vec();
+        let mut cursor = Cursor::new(content);
+
+        let mut in_string = false;
+        let mut in_keyword = false;
+
+        while cursor.pos < content.len() {
+            match cursor.curr {
+                // Escapes in strings, and character literals outside of them, e.g.: `\"`
+                b'\\' => cursor.advance_by(1),
+
+                // Strings are left untouched, they already contain space separated classes.
+                b'"' => {
+                    in_string = !in_string;
+                    in_keyword = false;
+                }
+                _ if in_string => {}
+
+                // The start of a keyword, e.g.: `:div.flex`
+                b':' if !in_keyword => {
+                    in_keyword = true;
+                    result[cursor.pos] = b' ';
+                }
+
+                // Keep decimals in utilities, e.g.: `:div.px-2.5`
+                b'.' if in_keyword && !is_decimal_point(content, cursor.pos) => {
+                    result[cursor.pos] = b' ';
+                }
+
+                c if c.is_ascii_whitespace()
+                    || matches!(c, b'[' | b']' | b'(' | b')' | b'{' | b'}' | b',') =>
+                {
+                    in_keyword = false;
+                }
+
+                _ => {}
+            }
+
+            cursor.advance_by(1);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str) -> String {
+        let actual = Clojure.process(input.as_bytes());
+        assert_eq!(actual.len(), input.len());
+        String::from_utf8(actual).unwrap()
+    }
+
+    #[test]
+    fn it_splits_tag_keyword_classes() {
+        assert_eq!(
+            run(r#"[:div.flex.gap-2 {:class "p-4"}]"#),
+            r#"[ div flex gap-2 { class "p-4"}]"#
+        );
+    }
+
+    #[test]
+    fn it_keeps_variants_and_decimals() {
+        assert_eq!(
+            run("[:a.px-2.5.hover:underline]"),
+            "[ a px-2.5 hover:underline]"
+        );
+    }
+
+    #[test]
+    fn it_splits_variants_that_start_with_a_digit() {
+        assert_eq!(run("[:div.p-2.2xl:p-4]"), "[ div p-2 2xl:p-4]");
+    }
+
+    #[test]
+    fn it_skips_character_literals() {
+        assert_eq!(
+            run(r#"(str \" "x") [:div.flex.p-4]"#),
+            r#"(str \" "x") [ div flex p-4]"#
+        );
+    }
+
+    #[test]
+    fn it_leaves_strings_untouched() {
+        assert_eq!(
+            run(r#"[:p "Hello: \"world.\" md:flex"]"#),
+            r#"[ p "Hello: \"world.\" md:flex"]"#
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::preprocessors::class_shorthand::replace_class_shorthand;
+use crate::preprocessors::PreProcessor;
+
+/// Haml allows chaining classes onto elements, e.g.: `%div.flex.items-center{class: "p-4"}`.
+#[derive(Debug, Default)]
+pub struct Haml;
+
+impl PreProcessor for Haml {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        replace_class_shorthand(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_class_shorthand() {
+        let actual = Haml.process(b"%div.hover:underline.md:flex{class: \"p-4\"}");
+        assert_eq!(
+            actual,
+            b"%div hover:underline md:flex{class: \"p-4\"}".to_vec()
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Pre-processors normalize template syntax before the content is handed to the `Extractor`.
+//!
+//! Every template language has its own quirks. Instead of teaching the `Extractor` about all of
+//! them, we rewrite the few language specific constructs into something the `Extractor` already
+//! understands. Pre-processors are looked up by file extension.
+//!
+//! Pre-processors must not change the byte length of the content, candidate positions are
+//! reported as offsets into the original content.
+
+mod class_shorthand;
+pub mod clojure;
+pub mod haml;
+pub mod pug;
+pub mod slim;
+pub mod svelte;
+
+pub use clojure::Clojure;
+pub use haml::Haml;
+pub use pug::Pug;
+pub use slim::Slim;
+pub use svelte::Svelte;
+
+pub trait PreProcessor: Sync + Send {
+    /// Rewrite the given content so that the `Extractor` can find all candidates in it.
+    fn process(&self, content: &[u8]) -> Vec<u8>;
+}
+
+/// Find the pre-processor that is registered for the given file extension (without the leading
+/// `.`).
+pub fn pre_processor_for(extension: &str) -> Option<&'static dyn PreProcessor> {
+    match extension {
+        "clj" | "cljc" | "cljs" => Some(&Clojure),
+        "haml" => Some(&Haml),
+        "pug" => Some(&Pug),
+        "slim" => Some(&Slim),
+        "svelte" => Some(&Svelte),
+        _ => None,
+    }
+}
+
+/// Run the pre-processor that is registered for the given extension. Content for extensions
+/// without a pre-processor is returned as-is.
+pub fn pre_process_input(content: Vec<u8>, extension: &str) -> Vec<u8> {
+    match pre_processor_for(extension) {
+        Some(pre_processor) => pre_processor.process(&content),
+        None => content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_returns_content_as_is_for_unknown_extensions() {
+        let input = b"<div class:px-4='condition'></div>".to_vec();
+        assert_eq!(pre_process_input(input.clone(), "html"), input);
+        assert_eq!(pre_process_input(input.clone(), ""), input);
+    }
+
+    #[test]
+    fn it_uses_the_registered_pre_processor() {
+        let input = b"<div class:px-4='condition'></div>".to_vec();
+        assert_eq!(
+            pre_process_input(input, "svelte"),
+            b"<div       px-4='condition'></div>".to_vec()
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::preprocessors::class_shorthand::replace_class_shorthand;
+use crate::preprocessors::PreProcessor;
+
+/// Pug allows chaining classes onto elements, e.g.: `div.flex.items-center(class="p-4")`.
+#[derive(Debug, Default)]
+pub struct Pug;
+
+impl PreProcessor for Pug {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        replace_class_shorthand(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_class_shorthand() {
+        let actual = Pug.process(b"nav.flex.gap-2\n  a.px-2.5(href=\"/\") Home");
+        assert_eq!(
+            actual,
+            b"nav flex gap-2\n  a px-2.5(href=\"/\") Home".to_vec()
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use crate::preprocessors::class_shorthand::replace_class_shorthand;
+use crate::preprocessors::PreProcessor;
+
+/// Slim allows chaining classes onto elements, e.g.: `div.flex.items-center class="p-4"`.
+#[derive(Debug, Default)]
+pub struct Slim;
+
+impl PreProcessor for Slim {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        replace_class_shorthand(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_class_shorthand() {
+        let actual = Slim.process(b".card.shadow-lg\n  p.text-sm.lg:text-base Hello");
+        assert_eq!(
+            actual,
+            b" card shadow-lg\n  p text-sm lg:text-base Hello".to_vec()
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use crate::preprocessors::PreProcessor;
+use bstr::ByteSlice;
+
+/// Svelte allows toggling classes with the `class:` directive, e.g.: `<div class:px-4={active}>`.
+/// We blank out the `class:` prefix so that the utility becomes a standalone candidate.
+#[derive(Debug, Default)]
+pub struct Svelte;
+
+impl PreProcessor for Svelte {
+    fn process(&self, content: &[u8]) -> Vec<u8> {
+        content.replace(" class:", "       ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_removes_the_class_directive() {
+        let actual = Svelte.process(b"<div class:px-4={active} class:underline>");
+        assert_eq!(
+            actual,
+            b"<div       px-4={active}       underline>".to_vec()
+        );
+    }
+}
@@ -1,5 +1,10 @@
-use ignore::{DirEntry, WalkBuilder};
-use std::{path::Path, sync};
+use fxhash::{FxHashMap, FxHashSet};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, Match, WalkBuilder};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{self, Arc, RwLock};
 
 static BINARY_EXTENSIONS: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new(|| {
     include_str!("fixtures/binary-extensions.txt")
@@ -25,40 +30,764 @@ static IGNORED_FILES: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new(||
 static IGNORED_CONTENT_DIRS: sync::LazyLock<Vec<&'static str>> =
     sync::LazyLock::new(|| vec![".git"]);
 
-#[tracing::instrument(skip(root))]
-pub fn resolve_allowed_paths(root: &Path) -> impl Iterator<Item = DirEntry> {
-    WalkBuilder::new(root)
-        .hidden(false)
-        .require_git(false)
-        .filter_entry(|entry| match entry.file_type() {
-            Some(file_type) if file_type.is_dir() => match entry.file_name().to_str() {
-                Some(dir) => !IGNORED_CONTENT_DIRS.contains(&dir),
-                None => false,
-            },
-            Some(file_type) if file_type.is_file() || file_type.is_symlink() => {
-                is_allowed_content_path(entry.path())
+/// A file with gitignore syntax, for files that should be committed but not scanned
+pub const TAILWIND_IGNORE_FILE: &str = ".tailwindignore";
+
+pub(crate) static DEFAULT_IGNORE_RULES: sync::LazyLock<IgnoreRules> =
+    sync::LazyLock::new(IgnoreRules::default);
+
+/// Extensions, file names and directory names
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    /// Extensions without the leading `.`, e.g.: `json`
+    pub extensions: Vec<String>,
+
+    /// File names, e.g.: `package-lock.json`
+    pub files: Vec<String>,
+
+    /// Directory names, e.g.: `vendor`
+    pub dirs: Vec<String>,
+}
+
+/// The paths that are never scanned during source detection. Starts from the built-in lists,
+/// which can be extended or reduced.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    extensions: FxHashSet<String>,
+    files: FxHashSet<String>,
+    dirs: FxHashSet<String>,
+
+    /// Skip files that look binary based on their content, regardless of their extension
+    sniff_content: bool,
+
+    /// Scan files without an extension, as long as their content doesn't look binary
+    extensionless_files: bool,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self {
+            extensions: IGNORED_EXTENSIONS
+                .iter()
+                .chain(BINARY_EXTENSIONS.iter())
+                .map(|x| x.to_string())
+                .collect(),
+            files: IGNORED_FILES.iter().map(|x| x.to_string()).collect(),
+            dirs: IGNORED_CONTENT_DIRS.iter().map(|x| x.to_string()).collect(),
+            sniff_content: false,
+            extensionless_files: false,
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Ignore the given extensions, files and directories as well
+    pub fn ignore(mut self, list: IgnoreList) -> Self {
+        self.extensions.extend(list.extensions);
+        self.files.extend(list.files);
+        self.dirs.extend(list.dirs);
+        self
+    }
+
+    /// Stop ignoring the given extensions, files and directories, e.g.: to scan files that are
+    /// ignored by default
+    pub fn unignore(mut self, list: IgnoreList) -> Self {
+        for extension in list.extensions {
+            self.extensions.remove(&extension);
+        }
+        for file in list.files {
+            self.files.remove(&file);
+        }
+        for dir in list.dirs {
+            self.dirs.remove(&dir);
+        }
+        self
+    }
+
+    /// Skip files that look binary based on their content, regardless of their extension
+    pub fn with_content_sniffing(mut self, sniff_content: bool) -> Self {
+        self.sniff_content = sniff_content;
+        self
+    }
+
+    /// Scan files without an extension, e.g.: extensionless templates. Their content is always
+    /// sniffed, so that binary files are still skipped.
+    pub fn with_extensionless_files(mut self, extensionless_files: bool) -> Self {
+        self.extensionless_files = extensionless_files;
+        self
+    }
+
+    pub fn sniff_content(&self) -> bool {
+        self.sniff_content
+    }
+
+    /// A description of the rules that doesn't depend on the order of the lists, e.g.: to tell
+    /// whether a cache was written with the same rules.
+    pub(crate) fn cache_key(&self) -> String {
+        fn sorted(set: &FxHashSet<String>) -> Vec<&String> {
+            let mut list: Vec<&String> = set.iter().collect();
+            list.sort();
+            list
+        }
+
+        format!(
+            "{:?} {:?} {:?} {} {}",
+            sorted(&self.extensions),
+            sorted(&self.files),
+            sorted(&self.dirs),
+            self.sniff_content,
+            self.extensionless_files
+        )
+    }
+
+    pub fn is_ignored_extension(&self, extension: &str) -> bool {
+        self.extensions.contains(extension)
+    }
+
+    pub fn is_ignored_dir(&self, name: &str) -> bool {
+        self.dirs.contains(name)
+    }
+
+    pub fn is_allowed_content_path(&self, path: &Path) -> bool {
+        // Skip known ignored files
+        if path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| self.files.contains(s))
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        // Skip known ignored extensions
+        path.extension()
+            .map(|s| s.to_str().unwrap_or_default())
+            .map(|ext| !self.is_ignored_extension(ext))
+            .unwrap_or(self.extensionless_files)
+    }
+
+    /// Same as `is_allowed_content_path`, but the content of the file is sniffed as well when
+    /// necessary.
+    pub fn is_allowed_content_file(&self, path: &Path) -> bool {
+        if !self.is_allowed_content_path(path) {
+            return false;
+        }
+
+        if self.sniff_content || path.extension().is_none() {
+            return !is_binary_file(path);
+        }
+
+        true
+    }
+}
+
+/// The number of bytes at the start of a file that are used to decide whether it is binary
+const SNIFF_SIZE: usize = 8 * 1024;
+
+/// Whether the content looks binary: it contains a NUL byte, or a large part of it is not valid
+/// UTF-8. Only the first block of the content is checked.
+pub fn is_binary(content: &[u8]) -> bool {
+    let block = &content[..content.len().min(SNIFF_SIZE)];
+
+    if block.contains(&0) {
+        return true;
+    }
+
+    let mut invalid = 0;
+    let mut rest = block;
+    while let Err(err) = std::str::from_utf8(rest) {
+        // A character that is cut off at the end of the block is not a problem
+        let Some(len) = err.error_len() else {
+            break;
+        };
+
+        invalid += len;
+        rest = &rest[err.valid_up_to() + len..];
+    }
+
+    // Text in a legacy encoding, e.g.: Latin-1, only has a few invalid bytes
+    invalid * 10 > block.len() * 3
+}
+
+/// Whether the file looks binary based on its first block. Files that can't be read are not
+/// binary, so that the error is reported when the file is read.
+pub fn is_binary_file(path: &Path) -> bool {
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+
+    let mut block = Vec::with_capacity(SNIFF_SIZE);
+    match file
+        .by_ref()
+        .take(SNIFF_SIZE as u64)
+        .read_to_end(&mut block)
+    {
+        Ok(_) => is_binary(&block),
+        Err(_) => false,
+    }
+}
+
+#[deprecated(note = "use `DetectSources` to find the sources in a directory")]
+#[tracing::instrument(skip(root, rules))]
+pub fn resolve_allowed_paths(root: &Path, rules: &IgnoreRules) -> impl Iterator<Item = DirEntry> {
+    let (builder, _) = allowed_paths_walker(&[root.to_path_buf()], rules, |_| {});
+    builder.build().filter_map(Result::ok)
+}
+
+/// A gitignore-aware walker over the roots, that only yields the directories and files that are
+/// allowed by the rules. Entries that are skipped, e.g.: because they are git ignored, are passed
+/// to `on_skip`. The ignore files that are read by the walk are collected in the returned
+/// `IgnoreMatcher`, once the walk is done it knows all directories that were walked.
+pub(crate) fn allowed_paths_walker(
+    roots: &[PathBuf],
+    rules: &IgnoreRules,
+    on_skip: impl Fn(&DirEntry) + Send + Sync + 'static,
+) -> (WalkBuilder, IgnoreMatcher) {
+    let matcher = IgnoreMatcher::new(roots, rules);
+
+    let mut builder = WalkBuilder::new(&roots[0]);
+    for root in &roots[1..] {
+        builder.add(root);
+    }
+
+    // The walker would skip ignored entries before we get to see them, so we handle the ignore
+    // files ourselves.
+    let walk_matcher = matcher.clone();
+    builder.standard_filters(false).filter_entry(move |entry| {
+        let is_allowed = walk_matcher.is_allowed_entry(entry);
+        if !is_allowed {
+            on_skip(entry);
+        }
+        is_allowed
+    });
+
+    (builder, matcher)
+}
+
+/// The ignore files of every directory that is walked, to check whether a walk yields a path
+/// without walking, or reading any ignore files, again.
+#[derive(Debug, Clone)]
+pub(crate) struct IgnoreMatcher {
+    rules: IgnoreRules,
+
+    /// The roots of the walk
+    roots: Vec<PathBuf>,
+
+    /// The global gitignore file, e.g.: `~/.config/git/ignore`
+    global: Arc<Gitignore>,
+
+    /// The ignore files of the walked directories, added by the threads of the walk
+    dirs: Arc<RwLock<FxHashMap<PathBuf, Arc<IgnoreFiles>>>>,
+}
+
+impl IgnoreMatcher {
+    fn new(roots: &[PathBuf], rules: &IgnoreRules) -> Self {
+        Self {
+            rules: rules.clone(),
+            roots: roots.to_vec(),
+            global: Arc::new(Gitignore::global().0),
+            dirs: Arc::new(RwLock::new(
+                roots
+                    .iter()
+                    .map(|root| (root.clone(), IgnoreFiles::with_parents(root)))
+                    .collect(),
+            )),
+        }
+    }
+
+    fn ignore_files(&self, dir: &Path) -> Option<Arc<IgnoreFiles>> {
+        self.dirs
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(dir)
+            .cloned()
+    }
+
+    /// The root that the path is walked from
+    pub(crate) fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .find(|root| path.starts_with(root))
+            .map(PathBuf::as_path)
+    }
+
+    /// Whether the directory was walked
+    pub(crate) fn is_walked_dir(&self, dir: &Path) -> bool {
+        self.dirs
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains_key(dir)
+    }
+
+    /// Whether the path is inside of a directory that is ignored by the rules, e.g.: `.git`. The
+    /// directories that contain the root, e.g.: `/srv/vendor/app`, are not ignored.
+    pub(crate) fn is_inside_ignored_dir(&self, path: &Path) -> bool {
+        let Some(relative) = self
+            .root_of(path)
+            .and_then(|root| path.strip_prefix(root).ok())
+        else {
+            return false;
+        };
+
+        relative
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .any(|name| self.rules.is_ignored_dir(name))
+    }
+
+    /// Whether a walk over the roots yields the path: it is allowed by the rules, and neither the
+    /// path nor any directory between the root and the path is ignored, e.g.: by a `.gitignore` or
+    /// `.tailwindignore` file. Directories that didn't exist during the walk have no ignore files.
+    pub(crate) fn is_walked_path(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(root) = self.root_of(path) else {
+            return false;
+        };
+
+        // The closest directory that was walked. The directories below it are new, or ignored.
+        let Some((dir, ignore_files)) = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root))
+            .find_map(|dir| {
+                self.ignore_files(dir)
+                    .map(|ignore_files| (dir, ignore_files))
+            })
+        else {
+            return false;
+        };
+
+        let Ok(relative) = path.strip_prefix(dir) else {
+            return false;
+        };
+
+        let mut current = dir.to_path_buf();
+        let mut components = relative.components().peekable();
+
+        while let Some(component) = components.next() {
+            current.push(component);
+
+            let is_path = components.peek().is_none();
+            let is_dir = !is_path || is_dir;
+
+            if is_dir
+                && !current
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| !self.rules.is_ignored_dir(name))
+            {
+                return false;
+            }
+
+            if ignore_files.is_ignored(&current, is_dir, &self.global) {
+                return false;
             }
-            _ => false,
+        }
+
+        is_dir || self.rules.is_allowed_content_path(path)
+    }
+
+    fn is_allowed_entry(&self, entry: &DirEntry) -> bool {
+        let Some(file_type) = entry.file_type() else {
+            return false;
+        };
+
+        // The parent is always walked before its entries
+        let Some(parent) = entry
+            .path()
+            .parent()
+            .and_then(|parent| self.ignore_files(parent))
+        else {
+            return false;
+        };
+
+        if file_type.is_dir() {
+            let allowed = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|dir| !self.rules.is_ignored_dir(dir))
+                && !parent.is_ignored(entry.path(), true, &self.global);
+
+            if allowed {
+                self.dirs
+                    .write()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .insert(
+                        entry.path().to_path_buf(),
+                        IgnoreFiles::new(entry.path(), Some(parent)),
+                    );
+            }
+
+            return allowed;
+        }
+
+        (file_type.is_file() || file_type.is_symlink())
+            && !parent.is_ignored(entry.path(), false, &self.global)
+            && self.rules.is_allowed_content_file(entry.path())
+    }
+}
+
+/// The ignore files of a directory, and of all of its parents. They are matched like `git` does:
+/// the closest ignore file that matches the path decides.
+#[derive(Debug)]
+struct IgnoreFiles {
+    dir: PathBuf,
+    parent: Option<Arc<IgnoreFiles>>,
+
+    /// `.tailwindignore`, it takes precedence over the other ignore files
+    tailwind_ignore: Gitignore,
+
+    /// `.ignore`
+    ignore: Gitignore,
+
+    /// `.gitignore`
+    git_ignore: Gitignore,
+
+    /// `.git/info/exclude`, when the directory is the root of a git repository
+    git_exclude: Gitignore,
+}
+
+impl IgnoreFiles {
+    fn new(dir: &Path, parent: Option<Arc<IgnoreFiles>>) -> Arc<Self> {
+        let git = dir.join(".git");
+
+        Arc::new(Self {
+            dir: dir.to_path_buf(),
+            parent,
+            tailwind_ignore: read_ignore_file(dir, &dir.join(TAILWIND_IGNORE_FILE)),
+            ignore: read_ignore_file(dir, &dir.join(".ignore")),
+            git_ignore: read_ignore_file(dir, &dir.join(".gitignore")),
+            git_exclude: match git.exists() {
+                true => read_ignore_file(dir, &git.join("info").join("exclude")),
+                false => Gitignore::empty(),
+            },
         })
-        .build()
-        .filter_map(Result::ok)
+    }
+
+    /// The ignore files of the directory and all of its parents, e.g.: for a base inside of a
+    /// git repository.
+    fn with_parents(dir: &Path) -> Arc<Self> {
+        let parent = std::path::absolute(dir)
+            .ok()
+            .and_then(|dir| dir.parent().map(Self::with_parents));
+
+        Self::new(dir, parent)
+    }
+
+    /// Whether the path, an entry of the directory, is ignored
+    fn is_ignored(&self, path: &Path, is_dir: bool, global: &Gitignore) -> bool {
+        let mut tailwind_ignore = Match::None;
+        let mut ignore = Match::None;
+        let mut git_ignore = Match::None;
+        let mut git_exclude = Match::None;
+
+        // The parents of a relative directory are absolute
+        let absolute = match path.is_relative() {
+            true => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            false => path.to_path_buf(),
+        };
+
+        let mut current = Some(self);
+        while let Some(files) = current {
+            let path = match files.dir.is_absolute() {
+                true => absolute.as_path(),
+                false => path,
+            };
+
+            if tailwind_ignore.is_none() {
+                tailwind_ignore = files.tailwind_ignore.matched(path, is_dir);
+            }
+            if ignore.is_none() {
+                ignore = files.ignore.matched(path, is_dir);
+            }
+            if git_ignore.is_none() {
+                git_ignore = files.git_ignore.matched(path, is_dir);
+            }
+            if git_exclude.is_none() {
+                git_exclude = files.git_exclude.matched(path, is_dir);
+            }
+
+            current = files.parent.as_deref();
+        }
+
+        tailwind_ignore
+            .or(ignore)
+            .or(git_ignore)
+            .or(git_exclude)
+            .or(global.matched(&absolute, is_dir))
+            .is_ignore()
+    }
+}
+
+/// Read an ignore file with gitignore syntax. A file that doesn't exist ignores nothing.
+fn read_ignore_file(dir: &Path, file: &Path) -> Gitignore {
+    if !file.is_file() {
+        return Gitignore::empty();
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    _ = builder.add(file);
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
 }
 
+/// Whether the path is allowed by the built-in ignore rules
+#[deprecated(note = "use `IgnoreRules::is_allowed_content_path`")]
 pub fn is_allowed_content_path(path: &Path) -> bool {
-    // Skip known ignored files
-    if path
-        .file_name()
-        .unwrap()
-        .to_str()
-        .map(|s| IGNORED_FILES.contains(&s))
-        .unwrap_or(false)
-    {
-        return false;
+    DEFAULT_IGNORE_RULES.is_allowed_content_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_binary_content() {
+        assert!(!is_binary(b""));
+        assert!(!is_binary(b"<div class=\"flex\"></div>"));
+        assert!(!is_binary(
+            "<p class=\"underline\">Crème brûlée 🔥</p>".as_bytes()
+        ));
+
+        // NUL bytes
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+
+        // Mostly invalid UTF-8
+        assert!(is_binary(&[0xff, 0xfe, 0x80, 0x81, b'a', 0xc0, 0xc1]));
+
+        // A few Latin-1 characters in otherwise plain text
+        assert!(!is_binary(b"<p class=\"flex\">Cr\xe8me br\xfbl\xe9e</p>"));
+
+        // A character that is cut off at the end of the block
+        let mut content = vec![b'a'; SNIFF_SIZE - 1];
+        content.extend("🔥".as_bytes());
+        assert!(!is_binary(&content));
+    }
+
+    fn create_files(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn relative_files(root: &Path, entries: impl Iterator<Item = DirEntry>) -> Vec<String> {
+        let mut files: Vec<_> = entries
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+            })
+            .map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn walk(root: &Path) -> Vec<String> {
+        let (builder, _) =
+            allowed_paths_walker(&[root.to_path_buf()], &IgnoreRules::default(), |_| {});
+        relative_files(root, builder.build().filter_map(Result::ok))
+    }
+
+    #[test]
+    fn it_applies_nested_gitignore_files() {
+        let dir = create_files(&[
+            (".gitignore", "/dist\n*.generated.html\n"),
+            ("index.html", ""),
+            ("index.generated.html", ""),
+            ("dist/index.html", ""),
+            ("src/.gitignore", "/dist\nlegacy/\n"),
+            ("src/index.html", ""),
+            ("src/index.generated.html", ""),
+            ("src/dist/index.html", ""),
+            ("src/legacy/index.html", ""),
+            ("src/nested/legacy/index.html", ""),
+            ("src/nested/dist/index.html", ""),
+        ]);
+
+        assert_eq!(
+            walk(dir.path()),
+            vec!["index.html", "src/index.html", "src/nested/dist/index.html"]
+        );
     }
 
-    // Skip known ignored extensions
-    path.extension()
-        .map(|s| s.to_str().unwrap_or_default())
-        .map(|ext| !IGNORED_EXTENSIONS.contains(&ext) && !BINARY_EXTENSIONS.contains(&ext))
-        .unwrap_or(false)
+    #[test]
+    fn it_applies_negated_patterns() {
+        let dir = create_files(&[
+            (".gitignore", "*.html\n!keep.html\n"),
+            ("index.html", ""),
+            ("keep.html", ""),
+            ("src/.gitignore", "!index.html\nkeep.html\n"),
+            ("src/index.html", ""),
+            ("src/keep.html", ""),
+            ("src/other.html", ""),
+        ]);
+
+        assert_eq!(walk(dir.path()), vec!["keep.html", "src/index.html"]);
+    }
+
+    #[test]
+    fn it_applies_negated_patterns_to_dirs() {
+        // A file can't be re-included when its directory is ignored
+        let dir = create_files(&[
+            (
+                ".gitignore",
+                "/vendor\n!/vendor/keep.html\n/build/*\n!/build/keep.html\n",
+            ),
+            ("vendor/keep.html", ""),
+            ("build/index.html", ""),
+            ("build/keep.html", ""),
+        ]);
+
+        assert_eq!(walk(dir.path()), vec!["build/keep.html"]);
+    }
+
+    #[test]
+    fn it_lets_gitignore_files_take_precedence_over_git_exclude() {
+        let dir = create_files(&[
+            (".git/info/exclude", "*.html\n"),
+            (".gitignore", "!index.html\n"),
+            ("index.html", ""),
+            ("other.html", ""),
+            ("src/index.html", ""),
+            ("src/other.html", ""),
+        ]);
+
+        assert_eq!(walk(dir.path()), vec!["index.html", "src/index.html"]);
+    }
+
+    #[test]
+    fn it_only_applies_git_exclude_in_git_repositories() {
+        let dir = create_files(&[
+            ("info/exclude", "*.html\n"),
+            ("index.html", ""),
+            ("repo/.git/info/exclude", "*.html\n"),
+            ("repo/index.html", ""),
+        ]);
+
+        assert_eq!(walk(dir.path()), vec!["index.html"]);
+    }
+
+    #[test]
+    fn it_lets_ignore_files_take_precedence_over_gitignore_files() {
+        let dir = create_files(&[
+            (".gitignore", "*.html\n"),
+            (".ignore", "!index.html\nother.vue\n"),
+            ("index.html", ""),
+            ("other.html", ""),
+            ("other.vue", ""),
+            ("src/.gitignore", "!*.html\n"),
+            ("src/index.html", ""),
+            ("src/other.html", ""),
+        ]);
+
+        // The closest `.gitignore` decides over a `.ignore` in a parent
+        assert_eq!(
+            walk(dir.path()),
+            vec!["index.html", "src/index.html", "src/other.html"]
+        );
+    }
+
+    #[test]
+    fn it_lets_tailwindignore_files_take_precedence_over_ignore_files() {
+        let dir = create_files(&[
+            (".ignore", "*.html\n"),
+            (TAILWIND_IGNORE_FILE, "!index.html\nother.vue\n"),
+            ("index.html", ""),
+            ("other.html", ""),
+            ("other.vue", ""),
+        ]);
+
+        assert_eq!(walk(dir.path()), vec!["index.html"]);
+    }
+
+    #[test]
+    fn it_lets_all_ignore_files_take_precedence_over_the_global_gitignore() {
+        let dir = create_files(&[
+            (".git/info/exclude", "!excluded.html\n"),
+            (".gitignore", "!gitignored.html\n"),
+            (".ignore", "!ignored.html\n"),
+            (TAILWIND_IGNORE_FILE, "!tailwindignored.html\n"),
+        ]);
+
+        let mut builder = GitignoreBuilder::new("");
+        builder.add_line(None, "*.html").unwrap();
+        let global = builder.build().unwrap();
+
+        let ignore_files = IgnoreFiles::with_parents(dir.path());
+        let is_ignored =
+            |name: &str| ignore_files.is_ignored(&dir.path().join(name), false, &global);
+
+        assert!(is_ignored("index.html"));
+        assert!(!is_ignored("excluded.html"));
+        assert!(!is_ignored("gitignored.html"));
+        assert!(!is_ignored("ignored.html"));
+        assert!(!is_ignored("tailwindignored.html"));
+
+        let nested = IgnoreFiles::new(&dir.path().join("src"), Some(ignore_files.clone()));
+        assert!(nested.is_ignored(&dir.path().join("src/index.html"), false, &global));
+        assert!(!nested.is_ignored(&dir.path().join("src/gitignored.html"), false, &global));
+    }
+
+    #[test]
+    fn it_walks_the_same_files_as_the_ignore_walker() {
+        let dir = create_files(&[
+            (".git/info/exclude", "*.svelte\n!src/keep.svelte\n"),
+            (".gitignore", "/dist\n*.generated.html\nlegacy/\n"),
+            (".ignore", "!index.generated.html\n*.vue\n"),
+            (TAILWIND_IGNORE_FILE, "!keep.vue\n/src/*.tsx\n"),
+            ("index.html", ""),
+            ("index.generated.html", ""),
+            ("other.generated.html", ""),
+            ("app.svelte", ""),
+            ("app.vue", ""),
+            ("keep.vue", ""),
+            ("dist/index.html", ""),
+            ("src/.gitignore", "!legacy/\n!*.svelte\n"),
+            ("src/.ignore", "!*.vue\n"),
+            ("src/app.svelte", ""),
+            ("src/keep.svelte", ""),
+            ("src/app.vue", ""),
+            ("src/app.tsx", ""),
+            ("src/legacy/index.html", ""),
+            ("src/nested/app.tsx", ""),
+            ("src/nested/legacy/index.html", ""),
+            ("src/nested/.tailwindignore", "*.html\n!index.html\n"),
+            ("src/nested/index.html", ""),
+            ("src/nested/other.html", ""),
+            ("packages/lib/.git/info/exclude", "*.html\n"),
+            ("packages/lib/index.html", ""),
+            ("packages/lib/app.tsx", ""),
+        ]);
+
+        // The walker that applied the ignore files before we handled them ourselves
+        let rules = IgnoreRules::default();
+        let entries = WalkBuilder::new(dir.path())
+            .hidden(false)
+            .require_git(false)
+            .add_custom_ignore_filename(TAILWIND_IGNORE_FILE)
+            .filter_entry(move |entry| match entry.file_type() {
+                Some(file_type) if file_type.is_dir() => entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|dir| !rules.is_ignored_dir(dir)),
+                Some(file_type) => file_type.is_file() || file_type.is_symlink(),
+                None => false,
+            })
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| DEFAULT_IGNORE_RULES.is_allowed_content_file(entry.path()));
+        let expected = relative_files(dir.path(), entries);
+
+        assert_eq!(walk(dir.path()), expected);
+    }
 }
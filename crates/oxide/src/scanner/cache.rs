@@ -0,0 +1,202 @@
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::event;
+
+/// The scan results of a single file, as they were stored in the cache file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub size: u64,
+
+    /// Modification time in nanoseconds since the UNIX epoch
+    pub mtime: u128,
+
+    /// Hash of the file content
+    pub hash: u64,
+
+    pub candidates: Vec<String>,
+}
+
+impl CacheEntry {
+    pub fn new(size: u64, mtime: SystemTime, hash: u64, mut candidates: Vec<String>) -> Self {
+        candidates.sort();
+
+        Self {
+            size,
+            mtime: to_nanos(mtime),
+            hash,
+            candidates,
+        }
+    }
+
+    /// Modification time of the file when it was cached
+    pub fn modified(&self) -> SystemTime {
+        UNIX_EPOCH
+            + Duration::new(
+                (self.mtime / 1_000_000_000) as u64,
+                (self.mtime % 1_000_000_000) as u32,
+            )
+    }
+}
+
+/// The version of oxide that writes the cache. Another version can extract other candidates from
+/// the same file, e.g.: because the `Extractor` or one of the pre-processors changed, so caches
+/// written by another version are ignored.
+const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    /// The version of oxide that wrote the cache
+    version: String,
+
+    /// Everything that changes which candidates are found in a file, e.g.: the extractor options
+    /// and the ignore rules
+    options: String,
+
+    entries: FxHashMap<String, CacheEntry>,
+}
+
+/// A persistent cache of scan results, so that files that didn't change since the last run don't
+/// have to be read again.
+#[derive(Debug, Clone, Default)]
+pub struct ScanCache {
+    /// Location of the cache file
+    path: PathBuf,
+
+    /// The options the cached candidates were extracted with
+    options: String,
+
+    /// Cache entries, keyed by path. The files of a scanner are canonical already.
+    entries: FxHashMap<String, CacheEntry>,
+}
+
+impl ScanCache {
+    /// Load the cache file. Missing, unreadable or outdated cache files, or cache files that were
+    /// written with other options, result in an empty cache.
+    #[tracing::instrument(skip_all)]
+    pub fn load(path: PathBuf, options: String) -> Self {
+        let entries = fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<CacheFile>(&content).ok())
+            .filter(|cache| cache.version == CACHE_VERSION && cache.options == options)
+            .map(|cache| cache.entries)
+            .unwrap_or_default();
+
+        event!(
+            tracing::Level::INFO,
+            "Loaded {:?} cache entries from {:?}",
+            entries.len(),
+            path
+        );
+
+        Self {
+            path,
+            options,
+            entries,
+        }
+    }
+
+    /// Use other options from now on. The cached entries can't be used anymore when the options
+    /// changed.
+    pub fn set_options(&mut self, options: String) {
+        if self.options != options {
+            self.options = options;
+            self.entries.clear();
+        }
+    }
+
+    /// Get the cached entry for the file. It's up to the caller to decide whether the file
+    /// changed since it was cached.
+    pub fn get(&self, file: &Path) -> Option<&CacheEntry> {
+        self.entries.get(&Self::key(file)?)
+    }
+
+    /// Replace all cache entries and write them to the cache file.
+    #[tracing::instrument(skip_all)]
+    pub fn write<'a>(&mut self, entries: impl IntoIterator<Item = (&'a Path, CacheEntry)>) {
+        let cache = CacheFile {
+            version: CACHE_VERSION.into(),
+            options: self.options.clone(),
+            entries: entries
+                .into_iter()
+                .filter_map(|(file, entry)| Some((Self::key(file)?, entry)))
+                .collect(),
+        };
+
+        let result = serde_json::to_vec(&cache)
+            .map_err(std::io::Error::from)
+            .and_then(|content| {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                // Write to a temporary file first, so that a process that is killed halfway
+                // through doesn't leave a corrupt cache file behind.
+                let tmp = self.path.with_extension("tmp");
+                fs::write(&tmp, content)?;
+                fs::rename(&tmp, &self.path)
+            });
+
+        if let Err(err) = result {
+            event!(
+                tracing::Level::ERROR,
+                "Failed to write cache file {:?}: {:?}",
+                self.path,
+                err
+            );
+        }
+
+        self.entries = cache.entries;
+    }
+
+    fn key(file: &Path) -> Option<String> {
+        file.to_str().map(Into::into)
+    }
+}
+
+fn to_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_round_trips_entries() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("index.html");
+        fs::write(&file, "flex").unwrap();
+
+        let options = "options".to_string();
+        let cache_file = dir.path().join("cache/oxide.json");
+        let mtime = fs::metadata(&file).unwrap().modified().unwrap();
+
+        let mut cache = ScanCache::load(cache_file.clone(), options.clone());
+        assert!(cache.get(&file).is_none());
+
+        cache.write([(
+            file.as_path(),
+            CacheEntry::new(4, mtime, 42, vec!["flex".to_string()]),
+        )]);
+
+        let mut cache = ScanCache::load(cache_file.clone(), options.clone());
+        let entry = cache.get(&file).unwrap();
+        assert_eq!(entry.size, 4);
+        assert_eq!(entry.modified(), mtime);
+        assert_eq!(entry.hash, 42);
+        assert_eq!(entry.candidates, vec!["flex"]);
+
+        // Caches written with other options are ignored
+        cache.set_options("other options".to_string());
+        assert!(cache.get(&file).is_none());
+
+        let cache = ScanCache::load(cache_file, "other options".to_string());
+        assert!(cache.get(&file).is_none());
+    }
+}
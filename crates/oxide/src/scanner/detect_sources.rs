@@ -1,14 +1,70 @@
-use crate::scanner::allowed_paths::{is_allowed_content_path, resolve_allowed_paths};
-use crate::GlobEntry;
-use fxhash::FxHashSet;
-use std::cmp::Ordering;
-use std::path::PathBuf;
-use std::sync;
-use walkdir::WalkDir;
+use crate::scanner::allowed_paths::{allowed_paths_walker, IgnoreMatcher, IgnoreRules};
+use crate::{GlobEntry, ScanError};
+use fxhash::{FxHashMap, FxHashSet};
+use ignore::{DirEntry, WalkState};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{self, mpsc};
+
+/// An entry of a walked directory, either allowed or skipped
+#[derive(Debug, Clone)]
+struct ListedEntry {
+    path: PathBuf,
+    is_dir: bool,
+    is_file: bool,
+}
+
+impl From<&DirEntry> for ListedEntry {
+    fn from(entry: &DirEntry) -> Self {
+        let file_type = entry.file_type();
+
+        Self {
+            path: entry.path().to_path_buf(),
+            is_dir: file_type.is_some_and(|file_type| file_type.is_dir()),
+            is_file: file_type.is_some_and(|file_type| file_type.is_file()),
+        }
+    }
+}
+
+/// Everything that is found in a single walk over the bases
+#[derive(Debug, Default)]
+struct Walk {
+    /// All allowed files
+    files: Vec<PathBuf>,
+
+    /// All allowed directories
+    dirs: Vec<PathBuf>,
+
+    /// The entries of every allowed directory, including the entries that are ignored. These are
+    /// needed to know where we can't use globs.
+    listings: FxHashMap<PathBuf, Vec<ListedEntry>>,
+
+    /// The ignore files of every allowed directory
+    ignore_matcher: Option<IgnoreMatcher>,
+}
+
+/// Everything that is detected in the bases
+#[derive(Debug, Default)]
+pub(crate) struct Detected {
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) globs: Vec<GlobEntry>,
+
+    /// All directories that were walked, e.g.: to watch them for new files
+    pub(crate) dirs: Vec<PathBuf>,
+
+    /// Checks paths the same way the walk did, without walking again
+    pub(crate) ignore_matcher: Option<IgnoreMatcher>,
+
+    pub(crate) errors: Vec<ScanError>,
+}
 
 #[derive(Debug, Clone)]
 pub struct DetectSources {
-    base: PathBuf,
+    /// Directories to detect sources in
+    bases: Vec<PathBuf>,
+
+    /// Paths that are never scanned
+    ignore_rules: IgnoreRules,
 }
 
 static KNOWN_EXTENSIONS: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new(|| {
@@ -24,49 +80,174 @@ static KNOWN_EXTENSIONS: sync::LazyLock<Vec<&'static str>> = sync::LazyLock::new
 
 impl DetectSources {
     pub fn new(base: PathBuf) -> Self {
-        Self { base }
+        Self {
+            bases: vec![base],
+            ignore_rules: IgnoreRules::default(),
+        }
     }
 
-    pub fn detect(&self) -> (Vec<PathBuf>, Vec<GlobEntry>) {
-        let (files, dirs) = self.resolve_files();
-        let globs = self.resolve_globs(&dirs);
+    /// Detect sources in another base as well. Bases inside of other bases are already covered by
+    /// their parent, so they are ignored.
+    pub fn with_base(mut self, base: PathBuf) -> Self {
+        self.bases.push(base);
+        self
+    }
+
+    pub fn with_ignore_rules(mut self, ignore_rules: IgnoreRules) -> Self {
+        self.ignore_rules = ignore_rules;
+        self
+    }
+
+    pub fn ignore_rules(&self) -> &IgnoreRules {
+        &self.ignore_rules
+    }
 
-        (files, globs)
+    /// Detect all files and globs in the bases. Bases that can't be read are reported, and don't
+    /// prevent detection in the other bases.
+    pub fn detect(&self) -> (Vec<PathBuf>, Vec<GlobEntry>, Vec<ScanError>) {
+        let detected = self.detect_all();
+
+        (detected.files, detected.globs, detected.errors)
     }
 
-    fn resolve_files(&self) -> (Vec<PathBuf>, Vec<PathBuf>) {
-        let mut files: Vec<PathBuf> = vec![];
-        let mut dirs: Vec<PathBuf> = vec![];
+    /// Same as `detect`, but includes the directories that were walked
+    pub(crate) fn detect_all(&self) -> Detected {
+        let mut bases = vec![];
+        let mut errors = vec![];
+
+        for base in self.unique_bases() {
+            match fs::read_dir(&base) {
+                Ok(_) => bases.push(base),
+                Err(err) => errors.push(ScanError::MissingBase {
+                    base,
+                    message: err.to_string(),
+                }),
+            }
+        }
 
-        for entry in resolve_allowed_paths(&self.base) {
-            let Some(file_type) = entry.file_type() else {
-                continue;
+        if bases.is_empty() {
+            return Detected {
+                errors,
+                ..Default::default()
             };
+        }
+
+        let walk = self.walk(&bases);
+        let globs = self.resolve_globs(&bases, &walk);
+
+        Detected {
+            files: walk.files,
+            globs,
+            dirs: walk.dirs,
+            ignore_matcher: walk.ignore_matcher,
+            errors,
+        }
+    }
+
+    /// The canonical bases without duplicates and bases that are nested inside of other bases, in
+    /// the order they were added. Walking canonical bases results in canonical files, the same as
+    /// the files that are matched by globs.
+    fn unique_bases(&self) -> Vec<PathBuf> {
+        // Compare canonical paths, so that e.g.: `./src` and `src` are the same base
+        let canonical: Vec<PathBuf> = self
+            .bases
+            .iter()
+            .map(|base| dunce::canonicalize(base).unwrap_or_else(|_| base.clone()))
+            .collect();
+
+        canonical
+            .iter()
+            .enumerate()
+            .filter(|(idx, base)| {
+                !canonical.iter().enumerate().any(|(other_idx, other)| {
+                    // Of two identical bases, the first one is kept
+                    match other == *base {
+                        true => other_idx < *idx,
+                        false => base.starts_with(other),
+                    }
+                })
+            })
+            .map(|(_, base)| base.clone())
+            .collect()
+    }
+
+    /// Walk all bases in parallel. Only allowed paths are walked, but the entries that are skipped
+    /// are listed as well, so that the globs can be resolved without walking the bases again.
+    #[tracing::instrument(skip_all)]
+    fn walk(&self, bases: &[PathBuf]) -> Walk {
+        let (sender, receiver) = mpsc::channel();
+
+        let skipped = sender.clone();
+        let (builder, ignore_matcher) =
+            allowed_paths_walker(bases, &self.ignore_rules, move |entry| {
+                _ = skipped.send((ListedEntry::from(entry), false, entry.depth()));
+            });
+
+        builder.build_parallel().run(|| {
+            let sender = sender.clone();
 
-            if file_type.is_file() {
-                files.push(entry.into_path());
-            } else if file_type.is_dir() {
-                dirs.push(entry.into_path());
+            Box::new(move |entry| {
+                if let Ok(entry) = entry {
+                    _ = sender.send((ListedEntry::from(&entry), true, entry.depth()));
+                }
+
+                WalkState::Continue
+            })
+        });
+
+        // The walker sends the skipped entries, the receiver is done once both are gone
+        drop(builder);
+        drop(sender);
+
+        let mut walk = Walk {
+            ignore_matcher: Some(ignore_matcher),
+            ..Default::default()
+        };
+        for (entry, is_allowed, depth) in receiver {
+            if is_allowed && entry.is_dir {
+                walk.dirs.push(entry.path.clone());
+            } else if is_allowed && entry.is_file {
+                walk.files.push(entry.path.clone());
+            }
+
+            // The bases are not listed in a walked directory
+            if depth > 0 {
+                if let Some(parent) = entry.path.parent() {
+                    walk.listings
+                        .entry(parent.to_path_buf())
+                        .or_default()
+                        .push(entry);
+                }
             }
         }
 
-        (files, dirs)
+        // The order of a parallel walk is not stable
+        walk.files.sort();
+        walk.dirs.sort();
+
+        walk
     }
 
-    fn resolve_globs(&self, dirs: &Vec<PathBuf>) -> Vec<GlobEntry> {
-        let allowed_paths = FxHashSet::from_iter(dirs);
+    /// Build globs for all bases. The bases don't overlap, so neither do their globs. All globs
+    /// share the same list of extensions, found in any of the bases.
+    #[tracing::instrument(skip_all)]
+    fn resolve_globs(&self, bases: &[PathBuf], walk: &Walk) -> Vec<GlobEntry> {
+        let allowed_paths: FxHashSet<&Path> = walk.dirs.iter().map(PathBuf::as_path).collect();
+        let allowed_files: FxHashSet<&Path> = walk.files.iter().map(PathBuf::as_path).collect();
 
         // A list of directory names where we can't use globs, but we should track each file
         // individually instead. This is because these directories are often used for both source and
         // destination files.
-        let mut forced_static_directories = vec![self.base.join("public")];
+        let mut forced_static_directories: FxHashSet<PathBuf> =
+            bases.iter().map(|base| base.join("public")).collect();
 
         // A list of known extensions + a list of extensions we found in the project.
-        let mut found_extensions =
-            FxHashSet::from_iter(KNOWN_EXTENSIONS.iter().map(|x| x.to_string()));
-
-        // All root directories.
-        let mut root_directories = FxHashSet::from_iter(vec![self.base.clone()]);
+        let found_extensions = FxHashSet::from_iter(
+            KNOWN_EXTENSIONS
+                .iter()
+                .filter(|x| !self.ignore_rules.is_ignored_extension(x))
+                .map(|x| x.to_string()),
+        );
 
         // All directories where we can safely use deeply nested globs to watch all files.
         // In other comments we refer to these as "deep glob directories" or similar.
@@ -81,51 +262,51 @@ impl DetectSources {
         // E.g.: `./src/*/*.{html,js}`
         let mut shallow_globable_directories: FxHashSet<PathBuf> = FxHashSet::default();
 
-        // Collect all valid paths from the root. This will already filter out ignored files, unknown
-        // extensions and binary files.
-        let mut it = WalkDir::new(&self.base)
-            // Sorting to make sure that we always see the directories before the files. Also sorting
-            // alphabetically by default.
-            .sort_by(
-                |a, z| match (a.file_type().is_dir(), z.file_type().is_dir()) {
-                    (true, false) => Ordering::Less,
-                    (false, true) => Ordering::Greater,
-                    _ => a.file_name().cmp(z.file_name()),
-                },
-            )
-            .into_iter();
-
-        loop {
-            // We are only interested in valid entries
-            let entry = match it.next() {
-                Some(Ok(entry)) => entry,
-                _ => break,
-            };
-
-            // Ignore known directories that we don't want to traverse into.
-            if entry.file_type().is_dir() && entry.file_name() == ".git" {
-                it.skip_current_dir();
-                continue;
+        // All directories that contain ignored files, e.g.: files listed in a `.tailwindignore`
+        // file, and all of their parents. A deep glob for any of these directories would match the
+        // ignored files, so only their nested directories can use deep globs.
+        let mut ignored_file_directories: FxHashSet<&Path> = FxHashSet::default();
+        for (dir, entries) in &walk.listings {
+            let has_ignored_files = entries.iter().any(|entry| {
+                entry.is_file
+                    && self.ignore_rules.is_allowed_content_path(&entry.path)
+                    && !allowed_files.contains(entry.path.as_path())
+            });
+
+            if has_ignored_files {
+                ignored_file_directories
+                    .extend(dir.ancestors().take_while(|ancestor| {
+                        bases.iter().any(|base| ancestor.starts_with(base))
+                    }));
             }
+        }
 
-            if entry.file_type().is_dir() {
-                // If we are in a directory where we know that we can't use any globs, then we have to
-                // track each file individually.
-                if forced_static_directories.contains(&entry.path().to_path_buf()) {
-                    forced_static_directories.push(entry.path().to_path_buf());
-                    root_directories.insert(entry.path().to_path_buf());
+        for base in bases {
+            // Visit all directories depth-first, and sorted alphabetically, so that parents are
+            // always visited before their children.
+            let mut stack = vec![base.clone()];
+
+            while let Some(path) = stack.pop() {
+                // Ignore known directories that we don't want to traverse into.
+                if path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| self.ignore_rules.is_ignored_dir(name))
+                {
                     continue;
                 }
 
-                // If we are in a directory where the parent is a forced static directory, then this
+                let is_forced_static = forced_static_directories.contains(&path)
+                    || path
+                        .parent()
+                        .is_some_and(|parent| forced_static_directories.contains(parent));
+
+                // If we are in a directory where we know that we can't use any globs, then we have to
+                // track each file individually. If the parent is a forced static directory, then this
                 // will become a forced static directory as well.
-                if forced_static_directories.contains(&entry.path().parent().unwrap().to_path_buf())
-                {
-                    forced_static_directories.push(entry.path().to_path_buf());
-                    root_directories.insert(entry.path().to_path_buf());
-                    continue;
+                if is_forced_static {
+                    forced_static_directories.insert(path.clone());
                 }
-
                 // If we are in a directory, and the directory is git ignored, then we don't have to
                 // descent into the directory. However, we have to make sure that we mark the _parent_
                 // directory as a shallow glob directory because using deep globs from any of the
@@ -134,8 +315,8 @@ impl DetectSources {
                 // Another important part is that if one of the ignored directories is a deep glob
                 // directory, then all of its parents (until the root) should be marked as shallow glob
                 // directories as well.
-                if !allowed_paths.contains(&entry.path().to_path_buf()) {
-                    let mut parent = entry.path().parent();
+                else if !allowed_paths.contains(path.as_path()) {
+                    let mut parent = path.parent();
                     while let Some(parent_path) = parent {
                         // If the parent is already marked as a valid deep glob directory, then we have
                         // to mark it as a shallow glob directory instead, because we won't be able to
@@ -146,7 +327,7 @@ impl DetectSources {
                         }
 
                         // If we reached the root, then we can stop.
-                        if parent_path == self.base {
+                        if parent_path == base {
                             break;
                         }
 
@@ -156,48 +337,54 @@ impl DetectSources {
                         parent = parent_path.parent();
                     }
 
-                    it.skip_current_dir();
                     continue;
                 }
-
                 // If we are in a directory that is not git ignored, then we can mark this directory as
                 // a valid deep glob directory. This is only necessary if any of its parents aren't
                 // marked as deep glob directories already.
-                let mut found_deep_glob_parent = false;
-                let mut parent = entry.path().parent();
-                while let Some(parent_path) = parent {
-                    // If we reached the root, then we can stop.
-                    if parent_path == self.base {
-                        break;
-                    }
+                else {
+                    let mut found_deep_glob_parent = false;
+                    let mut parent = path.parent();
+                    while let Some(parent_path) = parent {
+                        // If we reached the root, then we can stop.
+                        if parent_path == base {
+                            break;
+                        }
 
-                    // If the parent is already marked as a deep glob directory, then we can stop
-                    // because this glob will match the current directory already.
-                    if deep_globable_directories.contains(parent_path) {
-                        found_deep_glob_parent = true;
-                        break;
-                    }
+                        // If the parent is already marked as a deep glob directory, then we can stop
+                        // because this glob will match the current directory already.
+                        if deep_globable_directories.contains(parent_path) {
+                            found_deep_glob_parent = true;
+                            break;
+                        }
 
-                    parent = parent_path.parent();
-                }
+                        parent = parent_path.parent();
+                    }
 
-                // If we didn't find a deep glob directory parent, then we can mark this directory as a
-                // deep glob directory (unless it is the root).
-                if !found_deep_glob_parent && entry.path() != self.base {
-                    deep_globable_directories.insert(entry.path().to_path_buf());
+                    // If we didn't find a deep glob directory parent, then we can mark this directory as a
+                    // deep glob directory (unless it is the root).
+                    if !found_deep_glob_parent
+                        && &path != base
+                        && !ignored_file_directories.contains(path.as_path())
+                    {
+                        deep_globable_directories.insert(path.clone());
+                    }
                 }
-            }
-
-            // Handle allowed content paths
-            if is_allowed_content_path(entry.path())
-                && allowed_paths.contains(&entry.path().to_path_buf())
-            {
-                let path = entry.path();
 
-                // Collect the extension for future use when building globs.
-                if let Some(extension) = path.extension().and_then(|x| x.to_str()) {
-                    found_extensions.insert(extension.to_string());
-                }
+                // Directories that were not walked, e.g.: ignored directories inside of a forced
+                // static directory, have no entries we know of.
+                let Some(entries) = walk.listings.get(&path) else {
+                    continue;
+                };
+
+                // The stack is popped from the end, so push the directories in reverse order.
+                let mut dirs: Vec<&PathBuf> = entries
+                    .iter()
+                    .filter(|entry| entry.is_dir)
+                    .map(|entry| &entry.path)
+                    .collect();
+                dirs.sort_by(|a, z| z.file_name().cmp(&a.file_name()));
+                stack.extend(dirs.into_iter().cloned());
             }
         }
 
@@ -1,2 +1,4 @@
 pub mod allowed_paths;
+pub mod cache;
 pub mod detect_sources;
+pub mod watcher;
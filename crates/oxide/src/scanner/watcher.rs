@@ -0,0 +1,214 @@
+use crate::glob::get_fast_patterns;
+use crate::{CandidateChanges, ScanError, Scanner};
+use fxhash::{FxHashMap, FxHashSet};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::event;
+
+/// Watches the sources of a `Scanner` for changes. Watching stops when the `Watcher` is dropped.
+pub struct Watcher {
+    _dirs: Arc<Mutex<WatchedDirs>>,
+}
+
+impl std::fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watcher").finish_non_exhaustive()
+    }
+}
+
+/// The directories that are watched, and the watcher that watches them
+pub(crate) struct WatchedDirs {
+    watcher: RecommendedWatcher,
+    dirs: FxHashMap<PathBuf, RecursiveMode>,
+}
+
+/// The watched directories of a scanner, as long as its `Watcher` is alive. A clone of the
+/// scanner is not watched.
+#[derive(Debug, Default)]
+pub(crate) struct WatchHandle(Option<Weak<Mutex<WatchedDirs>>>);
+
+impl Clone for WatchHandle {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for WatchedDirs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchedDirs")
+            .field("dirs", &self.dirs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scanner {
+    /// Watch all sources of the scanner and re-scan files when they change. Events are debounced:
+    /// all changes that happen within `debounce` of each other are handled at once. The callback
+    /// is called with the candidates that were added or removed, but only when there are any.
+    pub fn watch<F>(
+        scanner: Arc<Mutex<Scanner>>,
+        debounce: Duration,
+        mut on_change: F,
+    ) -> Result<Watcher, ScanError>
+    where
+        F: FnMut(CandidateChanges) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let watched = Arc::new(Mutex::new(WatchedDirs {
+            watcher: notify::recommended_watcher(sender).map_err(watch_error)?,
+            dirs: FxHashMap::default(),
+        }));
+
+        {
+            let mut scanner = scanner.lock().unwrap_or_else(|err| err.into_inner());
+            scanner.prepare();
+            scanner.watch_handle = WatchHandle(Some(Arc::downgrade(&watched)));
+            scanner.sync_watched_dirs()?;
+        }
+
+        thread::spawn(move || {
+            // The channel is closed when the `Watcher` is dropped
+            while let Some(paths) = next_batch(&receiver, debounce) {
+                let mut scanner = scanner.lock().unwrap_or_else(|err| err.into_inner());
+                let changes = scanner.scan_changed_paths(paths);
+                drop(scanner);
+
+                if !changes.is_empty() {
+                    on_change(changes);
+                }
+            }
+        });
+
+        Ok(Watcher { _dirs: watched })
+    }
+
+    /// Watch the directories that are walked to detect sources, and stop watching the directories
+    /// that are not walked anymore. Directories that can't be watched are reported, and don't
+    /// prevent watching the other directories.
+    pub(crate) fn sync_watched_dirs(&mut self) -> Result<(), ScanError> {
+        let Some(watched) = self.watch_handle.0.as_ref().and_then(Weak::upgrade) else {
+            return Ok(());
+        };
+        let mut watched = watched.lock().unwrap_or_else(|err| err.into_inner());
+        let WatchedDirs { watcher, dirs } = &mut *watched;
+
+        let wanted = self.watch_dirs();
+
+        dirs.retain(|dir, mode| {
+            if wanted.get(dir) == Some(mode) {
+                return true;
+            }
+
+            // Fails when the directory was deleted, it is not watched anymore either way
+            _ = watcher.unwatch(dir);
+            false
+        });
+
+        let mut result = Ok(());
+        for (dir, mode) in wanted {
+            if dirs.contains_key(&dir) {
+                continue;
+            }
+
+            match watcher.watch(&dir, mode) {
+                Ok(()) => {
+                    event!(tracing::Level::INFO, "Watching {:?}", dir);
+                    dirs.insert(dir, mode);
+                }
+                Err(err) => {
+                    event!(tracing::Level::ERROR, "Failed to watch {:?}: {}", dir, err);
+                    result = result.and(Err(watch_error(err)));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// The directories to watch: every directory that was walked to detect sources, and the base
+    /// of every configured source. Walked directories are watched on their own, so that ignored
+    /// directories, e.g.: `node_modules`, are never watched. Configured sources are scanned even
+    /// when they are ignored, so their bases are watched recursively.
+    fn watch_dirs(&self) -> FxHashMap<PathBuf, RecursiveMode> {
+        let patterns = match &self.sources {
+            Some(sources) => get_fast_patterns(sources),
+            None => vec![],
+        };
+
+        let mut bases: Vec<PathBuf> = patterns
+            .into_iter()
+            .filter_map(|(base, _)| dunce::canonicalize(base).ok())
+            .collect();
+
+        // Parents are shorter than their children
+        bases.sort_by_key(|base| base.components().count());
+
+        let mut roots: Vec<PathBuf> = vec![];
+        for base in bases {
+            if !roots.iter().any(|root| base.starts_with(root)) {
+                roots.push(base);
+            }
+        }
+
+        let dirs: Vec<PathBuf> = self
+            .detected_dirs
+            .iter()
+            .filter(|dir| !roots.iter().any(|root| dir.starts_with(root)))
+            .cloned()
+            .collect();
+
+        roots
+            .into_iter()
+            .map(|root| (root, RecursiveMode::Recursive))
+            .chain(
+                dirs.into_iter()
+                    .map(|dir| (dir, RecursiveMode::NonRecursive)),
+            )
+            .collect()
+    }
+}
+
+fn watch_error(err: notify::Error) -> ScanError {
+    ScanError::Watch {
+        message: err.to_string(),
+    }
+}
+
+/// Wait for the next change, and collect all paths that change until no events happened for the
+/// `debounce` duration. Returns `None` when the channel is closed.
+fn next_batch(
+    receiver: &mpsc::Receiver<notify::Result<notify::Event>>,
+    debounce: Duration,
+) -> Option<Vec<PathBuf>> {
+    let mut paths: FxHashSet<PathBuf> = FxHashSet::default();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let event = match deadline {
+            // Nothing changed yet, so wait as long as it takes
+            None => receiver.recv().ok()?,
+            Some(deadline) => {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => event,
+                    Err(_) => break,
+                }
+            }
+        };
+
+        match event {
+            // Reading a file is not a change
+            Ok(event) if event.kind.is_access() => {}
+            Ok(event) => paths.extend(event.paths),
+            Err(err) => event!(tracing::Level::ERROR, "Failed to watch files: {:?}", err),
+        }
+
+        if !paths.is_empty() {
+            deadline = Some(Instant::now() + debounce);
+        }
+    }
+
+    Some(paths.into_iter().collect())
+}
@@ -0,0 +1,245 @@
+/// A location inside of the input string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// UTF-8 *BYTE* index
+    pub byte: usize,
+
+    /// UTF-16 *character* index
+    pub utf16: i64,
+
+    /// 1-based line number. `\n`, `\r\n` and `\r` are all treated as a single line break.
+    pub line: i64,
+
+    /// 1-based column, counted in UTF-16 characters
+    pub column: i64,
+}
+
+/// The `IndexConverter` is used to convert UTF-8 *BYTE* indexes to UTF-16
+/// *character* indexes and line/column positions
+#[derive(Clone)]
+pub struct IndexConverter<'a> {
+    input: &'a str,
+    curr_utf8: usize,
+    curr_utf16: usize,
+
+    /// 0-based line of `curr_utf8`
+    curr_line: usize,
+
+    /// UTF-16 index where the current line starts
+    curr_line_start: usize,
+}
+
+impl<'a> IndexConverter<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            curr_utf8: 0,
+            curr_utf16: 0,
+            curr_line: 0,
+            curr_line_start: 0,
+        }
+    }
+
+    /// Resolve the full position of the given UTF-8 *BYTE* index. Indexes past the end of the input
+    /// resolve to the end of the input.
+    pub fn position(&mut self, pos: usize) -> Position {
+        self.seek(pos);
+
+        Position {
+            byte: self.curr_utf8,
+            utf16: self.curr_utf16 as i64,
+            line: self.curr_line as i64 + 1,
+            column: (self.curr_utf16 - self.curr_line_start) as i64 + 1,
+        }
+    }
+
+    fn seek(&mut self, pos: usize) {
+        #[cfg(debug_assertions)]
+        if self.curr_utf8 > self.input.len() {
+            panic!("curr_utf8 points past the end of the input string");
+        }
+
+        if pos < self.curr_utf8 {
+            self.curr_utf8 = 0;
+            self.curr_utf16 = 0;
+            self.curr_line = 0;
+            self.curr_line_start = 0;
+        }
+
+        // SAFETY: No matter what `pos` is passed into this function `curr_utf8`
+        // will only ever be incremented up to the length of the input string.
+        //
+        // This eliminates a "potential" panic that cannot actually happen
+        let slice = unsafe { self.input.get_unchecked(self.curr_utf8..) };
+
+        for c in slice.chars() {
+            if self.curr_utf8 >= pos {
+                break;
+            }
+
+            self.curr_utf8 += c.len_utf8();
+            self.curr_utf16 += c.len_utf16();
+
+            // A `\r` followed by a `\n` is a single line break, which will be handled by the `\n`.
+            let is_line_break = match c {
+                '\n' => true,
+                '\r' => self.input.as_bytes().get(self.curr_utf8) != Some(&b'\n'),
+                _ => false,
+            };
+
+            if is_line_break {
+                self.curr_line += 1;
+                self.curr_line_start = self.curr_utf16;
+            }
+        }
+    }
+}
+
+/// The start and end of every candidate, in the order of the given candidates. The candidates are
+/// given with the UTF-8 *BYTE* index where they start, e.g.: as returned by
+/// `Scanner::get_candidates_with_positions`.
+pub fn candidate_ranges(
+    input: &str,
+    candidates: Vec<(String, usize)>,
+) -> Vec<(String, Position, Position)> {
+    // The converter starts over when it has to move backwards, so we convert the positions in
+    // order. Candidates can contain other candidates, e.g.: `[color:red]` and `color:red`, so the
+    // end of a candidate is found by moving forward from its start.
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by_key(|idx| candidates[*idx].1);
+
+    let mut converter = IndexConverter::new(input);
+    let mut ranges = vec![None; candidates.len()];
+
+    for idx in order {
+        let (candidate, position) = &candidates[idx];
+        let start = converter.position(*position);
+        let end = converter.clone().position(position + candidate.len());
+
+        ranges[idx] = Some((start, end));
+    }
+
+    candidates
+        .into_iter()
+        .zip(ranges)
+        .filter_map(|((candidate, _), range)| {
+            let (start, end) = range?;
+            Some((candidate, start, end))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_index_converter() {
+        let mut converter = IndexConverter::new("Hello 🔥🥳 world!");
+
+        let map = HashMap::from([
+            // hello<space>
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 5),
+            (6, 6),
+            // inside the 🔥
+            (7, 8),
+            (8, 8),
+            (9, 8),
+            (10, 8),
+            // inside the 🥳
+            (11, 10),
+            (12, 10),
+            (13, 10),
+            (14, 10),
+            // <space>world!
+            (15, 11),
+            (16, 12),
+            (17, 13),
+            (18, 14),
+            (19, 15),
+            (20, 16),
+            (21, 17),
+            // Past the end should return the last utf-16 character index
+            (22, 17),
+            (100, 17),
+        ]);
+
+        for (idx_utf8, idx_utf16) in map {
+            assert_eq!(converter.position(idx_utf8).utf16, idx_utf16);
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let input = "a\nb🔥c\r\nd\re";
+        let mut converter = IndexConverter::new(input);
+
+        let expected = [
+            // a
+            (0, 0, 1, 1),
+            // \n
+            (1, 1, 1, 2),
+            // b
+            (2, 2, 2, 1),
+            // 🔥
+            (3, 3, 2, 2),
+            // c
+            (7, 5, 2, 4),
+            // \r\n is a single line break
+            (8, 6, 2, 5),
+            (9, 7, 2, 6),
+            // d
+            (10, 8, 3, 1),
+            // \r
+            (11, 9, 3, 2),
+            // e
+            (12, 10, 4, 1),
+            // Past the end
+            (13, 11, 4, 2),
+            (100, 11, 4, 2),
+        ];
+
+        // Seek forwards, then backwards to make sure the converter resets properly
+        for (byte, utf16, line, column) in expected.iter().chain(expected.iter().rev()) {
+            let position = converter.position(*byte);
+
+            assert_eq!(
+                (position.utf16, position.line, position.column),
+                (*utf16, *line, *column),
+                "byte {}",
+                byte
+            );
+            assert_eq!(position.byte, (*byte).min(input.len()));
+        }
+    }
+
+    #[test]
+    fn test_candidate_ranges() {
+        let candidates = vec![
+            ("flex".to_string(), 17),
+            ("[color:red]".to_string(), 4),
+            ("color:red".to_string(), 5),
+        ];
+
+        let ranges: Vec<_> = candidate_ranges("🔥[color:red]\r\nflex", candidates)
+            .into_iter()
+            .map(|(candidate, start, end)| (candidate, start.utf16, end.utf16, end.line))
+            .collect();
+
+        assert_eq!(
+            ranges,
+            // The order of the candidates is kept
+            vec![
+                ("flex".to_string(), 15, 19, 2),
+                ("[color:red]".to_string(), 2, 13, 1),
+                ("color:red".to_string(), 3, 12, 1),
+            ]
+        );
+    }
+}
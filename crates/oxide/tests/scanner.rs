@@ -7,31 +7,90 @@ mod scanner {
     use tailwindcss_oxide::*;
     use tempfile::tempdir;
 
+    /// Create the files in a new temporary directory, and return the canonical directory, e.g.:
+    /// `/private/var/...` instead of `/var/...` on macOS, the same as the files of a scanner
+    fn create_files(paths_with_content: &[(&str, &str)]) -> path::PathBuf {
+        let dir = dunce::canonicalize(tempdir().unwrap().into_path()).unwrap();
+
+        for (path, contents) in paths_with_content {
+            // Ensure we use the right path separator for the current platform
+            let path = dir.join(path.replace('/', path::MAIN_SEPARATOR.to_string().as_str()));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        dir
+    }
+
+    /// Change the content of the file without changing its modification time, so that we can tell
+    /// whether the file is scanned again
+    fn rewrite_keeping_mtime(file: &path::Path, contents: &str) {
+        let mtime = fs::metadata(file).unwrap().modified().unwrap();
+        fs::write(file, contents).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    /// The files of the scanner, relative to the directory
+    fn relative_files(scanner: &mut Scanner, dir: &path::Path) -> Vec<String> {
+        let mut files: Vec<_> = scanner
+            .get_files()
+            .into_iter()
+            .map(|x| {
+                x.replace(&format!("{}{}", dir.display(), path::MAIN_SEPARATOR), "")
+                    // Normalize paths to use unix style separators
+                    .replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Wait until the watcher reports the changes, regardless of how the events are batched
+    fn expect_changes(
+        receiver: &std::sync::mpsc::Receiver<CandidateChanges>,
+        added: &[&str],
+        removed: &[&str],
+    ) {
+        let expected = CandidateChanges {
+            added: added.iter().map(|x| x.to_string()).collect(),
+            removed: removed.iter().map(|x| x.to_string()).collect(),
+        };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut changes = CandidateChanges::default();
+
+        while changes != expected {
+            let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+            let Ok(next) = receiver.recv_timeout(timeout) else {
+                panic!("Expected {:?}, but got {:?}", expected, changes);
+            };
+
+            changes.added.extend(next.added);
+            changes.removed.extend(next.removed);
+            changes.added.sort();
+            changes.removed.sort();
+        }
+    }
+
     fn scan_with_globs(
         paths_with_content: &[(&str, Option<&str>)],
         globs: Vec<&str>,
     ) -> (Vec<String>, Vec<String>) {
-        // Create a temporary working directory
-        let dir = tempdir().unwrap().into_path();
+        // Create a temporary working directory with the necessary files
+        let dir = create_files(
+            &paths_with_content
+                .iter()
+                .map(|(path, contents)| (*path, contents.unwrap_or_default()))
+                .collect::<Vec<_>>(),
+        );
 
         // Initialize this directory as a git repository
         let _ = Command::new("git").arg("init").current_dir(&dir).output();
 
-        // Create the necessary files
-        for (path, contents) in paths_with_content {
-            // Ensure we use the right path separator for the current platform
-            let path = dir.join(path.replace('/', path::MAIN_SEPARATOR.to_string().as_str()));
-            let parent = path.parent().unwrap();
-            if !parent.exists() {
-                fs::create_dir_all(parent).unwrap();
-            }
-
-            match contents {
-                Some(contents) => fs::write(path, contents).unwrap(),
-                None => fs::write(path, "").unwrap(),
-            }
-        }
-
         let base = format!("{}", dir.display());
 
         // Resolve all content paths for the (temporary) current working directory
@@ -183,7 +242,7 @@ mod scanner {
         ]);
         assert_eq!(globs, vec![
             "index.html",
-            "src/**/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+            "src/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
             "src/a.html",
             "src/b.html",
             "src/c.html"
@@ -273,32 +332,32 @@ mod scanner {
                 "bar.html",
                 "baz.html",
                 "foo.html",
-                "nested-a/**/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-a/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-a/bar.html",
                 "nested-a/baz.html",
                 "nested-a/foo.html",
-                "nested-b/**/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-b/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-b/deeply-nested/bar.html",
                 "nested-b/deeply-nested/baz.html",
                 "nested-b/deeply-nested/foo.html",
-                "nested-c/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-c/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-c/bar.html",
                 "nested-c/baz.html",
                 "nested-c/foo.html",
-                "nested-c/sibling-folder/**/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-c/sibling-folder/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-c/sibling-folder/bar.html",
                 "nested-c/sibling-folder/baz.html",
                 "nested-c/sibling-folder/foo.html",
-                "nested-d/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-d/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-d/bar.html",
                 "nested-d/baz.html",
                 "nested-d/foo.html",
-                "nested-d/very/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
-                "nested-d/very/deeply/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
-                "nested-d/very/deeply/nested/*/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-d/very/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-d/very/deeply/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-d/very/deeply/nested/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-d/very/deeply/nested/bar.html",
                 "nested-d/very/deeply/nested/baz.html",
-                "nested-d/very/deeply/nested/directory/**/*.{aspx,astro,cjs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "nested-d/very/deeply/nested/directory/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
                 "nested-d/very/deeply/nested/directory/again/foo.html",
                 "nested-d/very/deeply/nested/directory/bar.html",
                 "nested-d/very/deeply/nested/directory/baz.html",
@@ -364,4 +423,936 @@ mod scanner {
 
         assert_eq!(candidates, vec!["content-['foo.styl']"]);
     }
+
+    #[test]
+    fn it_should_pre_process_in_memory_content_based_on_the_extension() {
+        let mut scanner = Scanner::new(None, None);
+
+        let candidates = scanner.scan_content(vec![
+            ChangedContent {
+                file: None,
+                content: Some("<div class:px-4='condition'></div>".to_string()),
+                extension: "svelte".to_string(),
+            },
+            ChangedContent {
+                file: None,
+                content: Some("<div class:py-4='condition'></div>".to_string()),
+                extension: "html".to_string(),
+            },
+        ]);
+
+        assert_eq!(candidates, vec!["class:py-4", "condition", "div", "px-4"]);
+    }
+
+    #[test]
+    fn it_should_scan_class_shorthand_in_indentation_based_templates() {
+        let candidates = scan(&[
+            ("index.pug", Some("nav.flex.gap-2")),
+            ("index.haml", Some("%div.hover:underline")),
+            ("index.slim", Some(".px-2.5.md:px-4")),
+        ])
+        .1;
+
+        assert_eq!(
+            candidates,
+            vec![
+                "div",
+                "flex",
+                "gap-2",
+                "hover:underline",
+                "md:px-4",
+                "nav",
+                "px-2.5"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_scan_hiccup_keyword_classes() {
+        let (paths, candidates) = scan(&[
+            ("index.html", None),
+            ("src/app.cljs", Some(r#"[:div.flex.gap-2 {:class "p-4"}]"#)),
+        ]);
+
+        assert!(paths.contains(&"src/app.cljs".to_string()));
+        assert_eq!(candidates, vec!["class", "div", "flex", "gap-2", "p-4"]);
+    }
+
+    #[test]
+    fn it_should_track_which_files_use_which_candidates() {
+        let dir = create_files(&[("a.html", "flex underline"), ("b.html", "flex font-bold")]);
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir)), None);
+        scanner.scan();
+
+        let mut files = scanner.get_files();
+        files.sort();
+        let (a, b) = (files[0].clone(), files[1].clone());
+
+        assert_eq!(
+            scanner.get_files_for_candidate("flex"),
+            vec![a.clone(), b.clone()]
+        );
+        assert_eq!(
+            scanner.get_files_for_candidate("underline"),
+            vec![a.clone()]
+        );
+        assert!(scanner.get_files_for_candidate("italic").is_empty());
+        assert_eq!(
+            scanner.get_candidates_for_file(b.as_ref()),
+            vec!["flex", "font-bold"]
+        );
+
+        // Changing the content of a file updates the indexes
+        let new_candidates = scanner.scan_content(vec![ChangedContent {
+            file: Some(b.clone().into()),
+            content: Some("italic".to_string()),
+            extension: "html".to_string(),
+        }]);
+
+        assert_eq!(new_candidates, vec!["italic"]);
+        assert_eq!(scanner.get_candidates_for_file(b.as_ref()), vec!["italic"]);
+        assert_eq!(scanner.get_files_for_candidate("flex"), vec![a.clone()]);
+        assert!(scanner.get_files_for_candidate("font-bold").is_empty());
+        assert_eq!(scanner.get_files_for_candidate("italic"), vec![b.clone()]);
+    }
+
+    #[test]
+    fn it_should_remove_candidates_that_are_no_longer_used() {
+        let dir = create_files(&[("a.html", "flex underline"), ("b.html", "flex font-bold")]);
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir)), None);
+        assert_eq!(scanner.scan(), vec!["flex", "font-bold", "underline"]);
+
+        let mut files = scanner.get_files();
+        files.sort();
+        let (a, b) = (files[0].clone(), files[1].clone());
+
+        // `flex` is still used by `b.html`, and `italic` moved from in-memory content to a file
+        scanner.scan_content(vec![ChangedContent {
+            file: None,
+            content: Some("italic".to_string()),
+            extension: "html".to_string(),
+        }]);
+        let changes = scanner.scan_content_with_changes(vec![ChangedContent {
+            file: Some(a.clone().into()),
+            content: Some("italic underline-offset-2".to_string()),
+            extension: "html".to_string(),
+        }]);
+        assert_eq!(
+            changes,
+            CandidateChanges {
+                added: vec!["underline-offset-2".to_string()],
+                removed: vec!["underline".to_string()],
+            }
+        );
+
+        // Candidates from content without a file are never removed
+        let changes = scanner.remove_files(vec![a.into()]);
+        assert_eq!(
+            changes,
+            CandidateChanges {
+                added: vec![],
+                removed: vec!["underline-offset-2".to_string()],
+            }
+        );
+
+        // Deleted files are forgotten on the next scan
+        fs::remove_file(&b).unwrap();
+        assert_eq!(scanner.scan(), vec!["italic"]);
+        assert!(scanner.get_files().is_empty());
+    }
+
+    #[test]
+    fn it_should_restore_unchanged_files_from_the_cache_file() {
+        let dir = create_files(&[("src/index.html", "flex underline")]);
+        let file = dir.join("src/index.html");
+
+        let cache_file = dir.join("cache/oxide.json");
+        let new_scanner = || {
+            Scanner::new(Some(DetectSources::new(dir.join("src"))), None)
+                .with_cache_file(cache_file.clone())
+        };
+
+        assert_eq!(new_scanner().scan(), vec!["flex", "underline"]);
+        assert!(cache_file.exists());
+
+        // Keep the size and modification time. The next run can't tell that the file changed,
+        // which proves that the file isn't read again.
+        rewrite_keeping_mtime(&file, "grid underline");
+        assert_eq!(new_scanner().scan(), vec!["flex", "underline"]);
+
+        // Files that did change are read again
+        let mtime = fs::metadata(&file).unwrap().modified().unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime + std::time::Duration::from_secs(1))
+            .unwrap();
+
+        assert_eq!(new_scanner().scan(), vec!["grid", "underline"]);
+    }
+
+    #[test]
+    fn it_should_ignore_the_cache_file_when_the_options_changed() {
+        let dir = create_files(&[("index.html", "flex"), ("large.html", "underline")]);
+        let cache_file = dir.join("cache/oxide.json");
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None)
+            .with_cache_file(cache_file.clone());
+        assert_eq!(scanner.scan(), vec!["flex", "underline"]);
+        drop(scanner);
+
+        // The cached candidates of `large.html` can't be used, the file is too large now
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None)
+            .with_cache_file(cache_file.clone())
+            .with_file_limits(FileLimits {
+                max_file_size: Some(5),
+                ..Default::default()
+            });
+        assert_eq!(scanner.scan(), vec!["flex"]);
+    }
+
+    #[test]
+    fn it_should_ignore_the_cache_file_of_another_version() {
+        let dir = create_files(&[("src/index.html", "flex")]);
+        let file = dir.join("src/index.html");
+        let cache_file = dir.join("cache/oxide.json");
+        let new_scanner = || {
+            Scanner::new(Some(DetectSources::new(dir.join("src"))), None)
+                .with_cache_file(cache_file.clone())
+        };
+
+        assert_eq!(new_scanner().scan(), vec!["flex"]);
+
+        let version = format!("\"version\":\"{}\"", env!("CARGO_PKG_VERSION"));
+        let content = fs::read_to_string(&cache_file).unwrap();
+        assert!(content.contains(&version));
+        fs::write(
+            &cache_file,
+            content.replace(&version, "\"version\":\"0.0.0\""),
+        )
+        .unwrap();
+
+        // The file is read again, even though it looks unchanged
+        rewrite_keeping_mtime(&file, "grid");
+        assert_eq!(new_scanner().scan(), vec!["grid"]);
+    }
+
+    #[test]
+    fn it_should_only_write_the_cache_file_when_files_were_scanned() {
+        let dir = create_files(&[("index.html", "flex")]);
+        let cache_file = dir.join("cache/oxide.json");
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None)
+            .with_cache_file(cache_file.clone());
+        assert_eq!(scanner.scan(), vec!["flex"]);
+        assert!(!cache_file.exists());
+
+        scanner.flush_cache();
+        assert!(cache_file.exists());
+
+        // Nothing changed, so there is nothing to write
+        fs::remove_file(&cache_file).unwrap();
+        assert_eq!(scanner.scan(), vec!["flex"]);
+        drop(scanner);
+        assert!(!cache_file.exists());
+    }
+
+    #[test]
+    fn it_should_detect_changes_based_on_the_change_detection_strategy() {
+        let dir = create_files(&[("index.html", "flex")]);
+        let file = dir.join("index.html");
+
+        let scanner = |change_detection| {
+            Scanner::new(Some(DetectSources::new(dir.clone())), None)
+                .with_change_detection(change_detection)
+        };
+
+        let mut mtime = scanner(ChangeDetection::Mtime);
+        let mut size_and_mtime = scanner(ChangeDetection::SizeAndMtime);
+        let mut content_hash = scanner(ChangeDetection::ContentHash);
+        for scanner in [&mut mtime, &mut size_and_mtime, &mut content_hash] {
+            assert_eq!(scanner.scan(), vec!["flex"]);
+        }
+
+        // Same size, same modification time
+        rewrite_keeping_mtime(&file, "grid");
+        assert_eq!(mtime.scan(), vec!["flex"]);
+        assert_eq!(size_and_mtime.scan(), vec!["flex"]);
+        assert_eq!(content_hash.scan(), vec!["grid"]);
+
+        // Different size, same modification time
+        rewrite_keeping_mtime(&file, "hidden");
+        assert_eq!(mtime.scan(), vec!["flex"]);
+        assert_eq!(size_and_mtime.scan(), vec!["hidden"]);
+        assert_eq!(content_hash.scan(), vec!["hidden"]);
+    }
+
+    #[test]
+    fn it_should_watch_sources_for_changes() {
+        let dir = create_files(&[("src/a.html", "flex"), ("src/b.html", "flex underline")]);
+
+        let scanner = std::sync::Arc::new(std::sync::Mutex::new(Scanner::new(
+            Some(DetectSources::new(dir.clone())),
+            None,
+        )));
+        assert_eq!(scanner.lock().unwrap().scan(), vec!["flex", "underline"]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _watcher = Scanner::watch(
+            scanner.clone(),
+            std::time::Duration::from_millis(50),
+            move |changes| sender.send(changes).unwrap(),
+        )
+        .unwrap();
+
+        // New files are detected
+        fs::write(dir.join("src/c.html"), "grid").unwrap();
+        expect_changes(&receiver, &["grid"], &[]);
+
+        // Changed files are scanned again
+        fs::write(dir.join("src/b.html"), "flex italic").unwrap();
+        expect_changes(&receiver, &["italic"], &["underline"]);
+
+        // Removed files are forgotten
+        fs::remove_file(dir.join("src/c.html")).unwrap();
+        expect_changes(&receiver, &[], &["grid"]);
+
+        // Ignored files are not scanned
+        fs::write(dir.join("src/d.lock"), "hidden").unwrap();
+        fs::write(dir.join("src/a.html"), "flex block").unwrap();
+        expect_changes(&receiver, &["block"], &[]);
+
+        // Git ignored files are not scanned
+        fs::write(dir.join(".gitignore"), "src/e.html\n").unwrap();
+        fs::write(dir.join("src/e.html"), "hidden").unwrap();
+        fs::write(dir.join("src/a.html"), "flex inline").unwrap();
+        expect_changes(&receiver, &["inline"], &["block"]);
+
+        // New directories are detected, and watched for files that are created later
+        fs::create_dir(dir.join("src/nested")).unwrap();
+        fs::write(dir.join("src/nested/f.html"), "grid").unwrap();
+        expect_changes(&receiver, &["grid"], &[]);
+        fs::write(dir.join("src/nested/g.html"), "underline").unwrap();
+        expect_changes(&receiver, &["underline"], &[]);
+
+        assert!(!scanner
+            .lock()
+            .unwrap()
+            .get_files()
+            .iter()
+            .any(|file| file.ends_with("e.html")));
+    }
+
+    #[test]
+    fn it_should_watch_the_new_sources_when_the_sources_change() {
+        let dir = create_files(&[("a/index.html", "flex"), ("b/index.html", "italic")]);
+
+        let base = format!("{}", dir.display());
+        let glob = |pattern: &str| GlobEntry {
+            base: base.clone(),
+            pattern: pattern.to_string(),
+        };
+
+        let scanner = std::sync::Arc::new(std::sync::Mutex::new(Scanner::new(
+            None,
+            Some(vec![glob("a/**/*.html")]),
+        )));
+        assert_eq!(scanner.lock().unwrap().scan(), vec!["flex"]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _watcher = Scanner::watch(
+            scanner.clone(),
+            std::time::Duration::from_millis(50),
+            move |changes| sender.send(changes).unwrap(),
+        )
+        .unwrap();
+
+        let changes = scanner
+            .lock()
+            .unwrap()
+            .set_sources(Some(vec![glob("b/**/*.html")]));
+        assert_eq!(changes.candidates.removed, vec!["flex"]);
+        assert_eq!(scanner.lock().unwrap().scan(), vec!["italic"]);
+
+        // Files of the previous sources are not scanned anymore, files of the new ones are
+        fs::write(dir.join("a/about.html"), "grid").unwrap();
+        fs::write(dir.join("b/about.html"), "underline").unwrap();
+        expect_changes(&receiver, &["underline"], &[]);
+    }
+
+    #[test]
+    fn it_should_not_match_paths_that_are_ignored_by_ignore_files() {
+        let dir = create_files(&[
+            (".gitignore", "src/build/\n*.generated.tsx"),
+            ("index.html", "flex"),
+            ("src/components/button.tsx", "italic"),
+            ("src/.tailwindignore", "fixtures/"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+
+        assert!(scanner.matches(&dir.join("src/components/button.tsx")));
+        assert!(scanner.matches(&dir.join("src/components/card.tsx")));
+        assert!(!scanner.matches(&dir.join("src/components/card.generated.tsx")));
+        assert!(!scanner.matches(&dir.join("src/build/page.tsx")));
+        assert!(!scanner.matches(&dir.join("src/build/nested/page.tsx")));
+        assert!(!scanner.matches(&dir.join("src/components/fixtures/page.tsx")));
+    }
+
+    #[test]
+    fn it_should_match_paths_with_the_ignore_files_of_the_last_walk() {
+        let dir = create_files(&[
+            (".gitignore", "*.generated.tsx"),
+            ("src/components/button.tsx", "italic"),
+        ]);
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+        assert!(scanner.matches(&dir.join("src/components/card.tsx")));
+
+        // Ignore files are only read again when the sources are refreshed
+        fs::write(dir.join("src/.gitignore"), "card.tsx").unwrap();
+        fs::remove_file(dir.join(".gitignore")).unwrap();
+        assert!(scanner.matches(&dir.join("src/components/card.tsx")));
+        assert!(!scanner.matches(&dir.join("src/components/card.generated.tsx")));
+
+        scanner.refresh();
+        assert!(!scanner.matches(&dir.join("src/components/card.tsx")));
+        assert!(scanner.matches(&dir.join("src/components/card.generated.tsx")));
+
+        // Directories that were not walked are checked with the ignore files of their parents
+        assert!(scanner.matches(&dir.join("src/new/button.tsx")));
+        assert!(!scanner.matches(&dir.join("src/new/card.tsx")));
+    }
+
+    #[test]
+    fn it_should_only_ignore_dirs_inside_of_the_base() {
+        use scanner::allowed_paths::{IgnoreList, IgnoreRules};
+
+        let dir = create_files(&[
+            ("vendor/app/index.html", "flex"),
+            ("vendor/app/src/button.tsx", "italic"),
+        ]);
+
+        let rules = IgnoreRules::default().ignore(IgnoreList {
+            dirs: vec!["vendor".into()],
+            ..Default::default()
+        });
+
+        let base = dir.join("vendor/app");
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(base.clone()).with_ignore_rules(rules)),
+            None,
+        );
+
+        assert_eq!(scanner.scan(), vec!["flex", "italic"]);
+        assert!(scanner.matches(&base.join("index.html")));
+        assert!(scanner.matches(&base.join("src/card.tsx")));
+        assert!(!scanner.matches(&base.join("src/vendor/card.tsx")));
+    }
+
+    #[test]
+    fn it_should_report_diagnostics_instead_of_failing() {
+        let dir = create_files(&[("index.html", "flex")]);
+
+        let base = format!("{}", dir.display());
+        let missing = dir.join("missing");
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(missing.clone())),
+            Some(vec![GlobEntry {
+                base: base.clone(),
+                pattern: "src/[a-".to_string(),
+            }]),
+        );
+
+        assert!(scanner.scan().is_empty());
+        let diagnostics = scanner.diagnostics();
+        assert!(matches!(
+            &diagnostics[..],
+            [
+                ScanError::MissingBase { base, .. },
+                ScanError::InvalidGlob { .. },
+                ..
+            ] if base == &missing
+        ));
+
+        // Unreadable files are reported until they can be read again
+        let file = dir.join("unreadable.html");
+        scanner.scan_content(vec![ChangedContent {
+            file: Some(file.clone()),
+            content: None,
+            extension: String::new(),
+        }]);
+        assert!(scanner
+            .diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.path() == Some(file.as_path())));
+
+        assert!(matches!(
+            scanner.get_candidates_with_positions(ChangedContent {
+                file: Some(file.clone()),
+                content: None,
+                extension: String::new(),
+            }),
+            Err(ScanError::ReadFile { .. })
+        ));
+
+        fs::write(&file, "underline").unwrap();
+        assert_eq!(
+            scanner.scan_content(vec![ChangedContent {
+                file: Some(file.clone()),
+                content: None,
+                extension: String::new(),
+            }]),
+            vec!["underline"]
+        );
+        assert!(!scanner
+            .diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.path() == Some(file.as_path())));
+    }
+
+    #[test]
+    fn it_should_scan_valid_globs_next_to_invalid_ones() {
+        let dir = create_files(&[
+            ("index.html", "flex"),
+            ("src/a.html", "underline"),
+            ("other/b.html", "italic"),
+        ]);
+
+        let base = format!("{}", dir.display());
+        let sources = ["*.html", "[a-", "src/*.html", "other/[a-"]
+            .iter()
+            .map(|pattern| GlobEntry {
+                base: base.clone(),
+                pattern: pattern.to_string(),
+            })
+            .collect();
+        let mut scanner = Scanner::new(None, Some(sources));
+
+        assert_eq!(scanner.scan(), vec!["flex", "italic", "underline"]);
+        assert_eq!(
+            scanner
+                .diagnostics()
+                .iter()
+                .filter(|diagnostic| matches!(diagnostic, ScanError::InvalidGlob { .. }))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn it_should_scan_in_a_single_call() {
+        let dir = create_files(&[
+            ("src/index.html", "flex"),
+            ("content/post.txt", "underline"),
+        ]);
+
+        let base = format!("{}", dir.display());
+        let result = tailwindcss_oxide::scan(ScanOptions {
+            base: Some(base.clone()),
+            sources: vec![GlobEntry {
+                base: base.clone(),
+                pattern: "content/*.txt".to_string(),
+            }],
+        });
+
+        assert_eq!(result.candidates, vec!["flex", "underline"]);
+        assert_eq!(result.files.len(), 2);
+        assert!(result
+            .globs
+            .iter()
+            .any(|glob| glob.pattern == "*.txt" && glob.base.ends_with("content")));
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn it_should_use_custom_ignore_rules() {
+        use scanner::allowed_paths::{IgnoreList, IgnoreRules};
+
+        let dir = create_files(&[
+            ("index.html", "flex"),
+            ("data.lock", "underline"),
+            ("vendor/lib.html", "italic"),
+        ]);
+
+        let rules = IgnoreRules::default()
+            .ignore(IgnoreList {
+                dirs: vec!["vendor".to_string()],
+                ..Default::default()
+            })
+            .unignore(IgnoreList {
+                extensions: vec!["lock".to_string()],
+                ..Default::default()
+            });
+
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(dir.clone()).with_ignore_rules(rules)),
+            None,
+        );
+
+        assert_eq!(scanner.scan(), vec!["flex", "underline"]);
+        assert_eq!(
+            relative_files(&mut scanner, &dir),
+            vec!["data.lock", "index.html"]
+        );
+    }
+
+    #[test]
+    fn it_should_respect_tailwindignore_files() {
+        let globs = test(&[
+            ("index.html", None),
+            (".tailwindignore", Some("fixtures/")),
+            ("fixtures/a.html", None),
+            ("src/b.html", None),
+            ("src/.tailwindignore", Some("*.fixture.html")),
+            ("src/c.fixture.html", None),
+            ("src/components/d.html", None),
+        ]);
+
+        assert_eq!(
+            globs,
+            vec![
+                "index.html",
+                "src/b.html",
+                // `src` itself can't use a glob, because it would match `src/c.fixture.html`
+                "src/components/**/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "src/components/d.html",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_let_tailwindignore_files_take_precedence_over_gitignore_files() {
+        let globs = test(&[
+            ("index.html", None),
+            (".gitignore", Some("*.fixture.html\ngenerated/")),
+            ("src/.tailwindignore", Some("!*.fixture.html")),
+            ("src/a.fixture.html", None),
+            ("src/generated/b.html", None),
+            ("c.fixture.html", None),
+        ]);
+
+        assert_eq!(
+            globs,
+            vec![
+                "index.html",
+                "src/*/*.{aspx,astro,cjs,clj,cljc,cljs,cts,eex,erb,gjs,gts,haml,handlebars,hbs,heex,html,jade,js,jsx,liquid,md,mdx,mjs,mts,mustache,njk,nunjucks,php,pug,py,razor,rb,rhtml,rs,slim,svelte,tpl,ts,tsx,twig,vue}",
+                "src/a.fixture.html",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_skip_binary_files_based_on_their_content() {
+        use scanner::allowed_paths::IgnoreRules;
+
+        let dir = create_files(&[
+            ("index.html", "flex"),
+            ("image.html", "underline \0\0"),
+            ("Template", "italic"),
+            ("blob", "\0\0font-bold\0"),
+        ]);
+
+        // Only the extension is used by default
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+        assert_eq!(scanner.scan(), vec!["flex", "underline"]);
+        assert_eq!(
+            relative_files(&mut scanner, &dir),
+            vec!["image.html", "index.html"]
+        );
+
+        let rules = IgnoreRules::default()
+            .with_content_sniffing(true)
+            .with_extensionless_files(true);
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(dir.clone()).with_ignore_rules(rules)),
+            None,
+        );
+        assert_eq!(scanner.scan(), vec!["flex", "italic"]);
+        assert_eq!(
+            relative_files(&mut scanner, &dir),
+            vec!["Template", "index.html"]
+        );
+
+        // Files that are scanned explicitly are sniffed as well
+        assert!(scanner
+            .scan_content(vec![ChangedContent {
+                file: Some(dir.join("image.html")),
+                content: None,
+                extension: String::new(),
+            }])
+            .is_empty());
+    }
+
+    #[test]
+    fn it_should_skip_large_and_minified_files() {
+        let dir = create_files(&[
+            ("index.html", "flex\nitalic\n"),
+            ("large.html", &"underline ".repeat(100)),
+            ("bundle.js", &"font-bold ".repeat(30)),
+        ]);
+
+        // Nothing is skipped by default
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+        assert_eq!(
+            scanner.scan(),
+            vec!["flex", "font-bold", "italic", "underline"]
+        );
+        assert!(scanner.diagnostics().is_empty());
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None)
+            .with_file_limits(FileLimits {
+                max_file_size: Some(500),
+                max_average_line_length: Some(200),
+            });
+        assert_eq!(scanner.scan(), vec!["flex", "italic"]);
+
+        let diagnostics = scanner.diagnostics();
+        assert_eq!(
+            diagnostics,
+            vec![
+                ScanError::MinifiedFile {
+                    file: dir.join("bundle.js"),
+                    average_line_length: 300,
+                    max_average_line_length: 200,
+                },
+                ScanError::FileTooLarge {
+                    file: dir.join("large.html"),
+                    size: 1000,
+                    max_size: 500,
+                },
+            ]
+        );
+
+        // Candidates of a file that starts exceeding the limits are removed
+        fs::write(dir.join("index.html"), "flex ".repeat(200)).unwrap();
+        let changes = scanner.scan_content_with_changes(vec![ChangedContent {
+            file: Some(dir.join("index.html")),
+            content: None,
+            extension: String::new(),
+        }]);
+        assert_eq!(changes.removed, vec!["flex", "italic"]);
+        assert_eq!(scanner.diagnostics().len(), 3);
+    }
+
+    #[test]
+    fn it_should_detect_sources_in_multiple_bases() {
+        let dir = create_files(&[
+            ("apps/web/index.html", "flex"),
+            ("apps/web/src/page.html", "italic"),
+            ("packages/ui/src/button.tsx", "underline"),
+            ("packages/ui/src/card.vue", "font-bold"),
+            ("packages/other/index.html", "hidden"),
+        ]);
+
+        let missing = dir.join("apps/missing");
+        let detect_sources = DetectSources::new(dir.join("apps/web"))
+            .with_base(dir.join("packages/ui"))
+            // Already covered by the other bases
+            .with_base(dir.join("apps/web/src"))
+            .with_base(dir.join("packages/ui/"))
+            .with_base(missing.clone());
+
+        let mut scanner = Scanner::new(Some(detect_sources), None);
+        assert_eq!(
+            scanner.scan(),
+            vec!["flex", "font-bold", "italic", "underline"]
+        );
+
+        let mut files = scanner.get_files();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                dir.join("apps/web/index.html").display().to_string(),
+                dir.join("apps/web/src/page.html").display().to_string(),
+                dir.join("packages/ui/src/button.tsx").display().to_string(),
+                dir.join("packages/ui/src/card.vue").display().to_string(),
+            ]
+        );
+
+        // A single deep glob per base directory, with the same extensions
+        let mut globs: Vec<_> = scanner
+            .get_globs()
+            .into_iter()
+            .map(|glob| (glob.base, glob.pattern))
+            .collect();
+        globs.sort();
+        assert_eq!(globs.len(), 2);
+        assert_eq!(globs[0].0, dir.join("apps/web/src").display().to_string());
+        assert_eq!(
+            globs[1].0,
+            dir.join("packages/ui/src").display().to_string()
+        );
+        assert_eq!(globs[0].1, globs[1].1);
+
+        assert!(matches!(
+            &scanner.diagnostics()[..],
+            [ScanError::MissingBase { base, .. }] if base == &missing
+        ));
+    }
+
+    #[test]
+    fn it_should_refresh_the_files_without_forgetting_unchanged_files() {
+        let dir = create_files(&[("index.html", "flex"), ("src/a.html", "italic")]);
+
+        let mut scanner = Scanner::new(Some(DetectSources::new(dir.clone())), None);
+
+        // Nothing was resolved yet, so all files are new
+        assert_eq!(
+            scanner.refresh(),
+            FileChanges {
+                added: vec![dir.join("index.html"), dir.join("src/a.html")],
+                ..Default::default()
+            }
+        );
+        assert_eq!(scanner.scan(), vec!["flex", "italic"]);
+        assert!(scanner.refresh().is_empty());
+
+        rewrite_keeping_mtime(&dir.join("index.html"), "grid");
+
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::write(dir.join("src/nested/b.html"), "underline").unwrap();
+        fs::write(dir.join("about.html"), "hidden").unwrap();
+        fs::remove_file(dir.join("src/a.html")).unwrap();
+
+        assert_eq!(
+            scanner.refresh(),
+            FileChanges {
+                added: vec![dir.join("about.html"), dir.join("src/nested/b.html")],
+                removed: vec![dir.join("src/a.html")],
+                candidates: CandidateChanges {
+                    added: vec![],
+                    removed: vec!["italic".into()],
+                },
+            }
+        );
+
+        // The candidates of removed files are forgotten right away, new files are scanned next
+        assert_eq!(
+            scanner.get_files_for_candidate("italic"),
+            Vec::<String>::new()
+        );
+        assert_eq!(scanner.scan(), vec!["flex", "hidden", "underline"]);
+    }
+
+    #[test]
+    fn it_should_reuse_candidates_when_the_sources_change() {
+        let dir = create_files(&[("a/index.html", "flex"), ("b/index.html", "italic")]);
+
+        let base = format!("{}", dir.display());
+        let glob = |pattern: &str| GlobEntry {
+            base: base.clone(),
+            pattern: pattern.to_string(),
+        };
+
+        let mut scanner = Scanner::new(None, Some(vec![glob("a/**/*.html")]));
+        assert_eq!(scanner.scan(), vec!["flex"]);
+
+        rewrite_keeping_mtime(&dir.join("a/index.html"), "grid");
+
+        assert_eq!(
+            scanner.set_sources(Some(vec![glob("a/**/*.html"), glob("b/**/*.html")])),
+            FileChanges {
+                added: vec![dir.join("b/index.html")],
+                ..Default::default()
+            }
+        );
+        assert_eq!(scanner.scan(), vec!["flex", "italic"]);
+
+        assert_eq!(
+            scanner.set_sources(Some(vec![glob("b/**/*.html")])),
+            FileChanges {
+                added: vec![],
+                removed: vec![dir.join("a/index.html")],
+                candidates: CandidateChanges {
+                    added: vec![],
+                    removed: vec!["flex".into()],
+                },
+            }
+        );
+        assert_eq!(scanner.scan(), vec!["italic"]);
+
+        // Detected files that are matched by the glob as well are not new
+        let changes = scanner.set_detect_sources(Some(DetectSources::new(dir.clone())));
+        assert_eq!(changes.added, vec![dir.join("a/index.html")]);
+        assert!(changes.removed.is_empty());
+        assert_eq!(scanner.scan(), vec!["grid", "italic"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_should_not_list_files_twice_when_the_bases_are_not_canonical() {
+        let dir = create_files(&[("src/index.html", "flex")]);
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(dir.join("src"), &link).unwrap();
+
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(link.clone())),
+            Some(vec![GlobEntry {
+                base: dir.join("src").display().to_string(),
+                pattern: "**/*.html".into(),
+            }]),
+        );
+
+        assert_eq!(scanner.scan(), vec!["flex"]);
+        assert_eq!(
+            scanner.get_files(),
+            vec![dir.join("src/index.html").display().to_string()]
+        );
+    }
+
+    #[test]
+    fn it_should_match_paths_against_the_sources() {
+        use scanner::allowed_paths::{IgnoreList, IgnoreRules};
+
+        let dir = create_files(&[
+            ("index.html", "flex"),
+            ("src/index.html", "italic"),
+            ("src/generated/index.html", "hidden"),
+            ("data/classes.lock", "underline"),
+        ]);
+
+        let rules = IgnoreRules::default().ignore(IgnoreList {
+            dirs: vec!["generated".into()],
+            ..Default::default()
+        });
+
+        let mut scanner = Scanner::new(
+            Some(DetectSources::new(dir.clone()).with_ignore_rules(rules)),
+            // Explicit sources are scanned, even though `lock` files are ignored
+            Some(vec![GlobEntry {
+                base: dir.display().to_string(),
+                pattern: "data/*.lock".into(),
+            }]),
+        );
+
+        assert!(scanner.matches(&dir.join("index.html")));
+        assert!(scanner.matches(&dir.join("src/index.html")));
+        assert!(scanner.matches(&dir.join("data/classes.lock")));
+
+        // Files that don't exist yet
+        assert!(scanner.matches(&dir.join("data/other.lock")));
+        assert!(!scanner.matches(&dir.join("src/other.lock")));
+        assert!(scanner.matches(&dir.join("src/components/button.tsx")));
+        assert!(!scanner.matches(&dir.join("about.html")));
+        assert!(!scanner.matches(&dir.join("src/index.css")));
+        assert!(!scanner.matches(&dir.join("src/generated/index.html")));
+
+        assert_eq!(
+            scanner.filter(vec![
+                dir.join("index.html"),
+                dir.join("package.json"),
+                dir.join("src/page.html"),
+            ]),
+            vec![dir.join("index.html"), dir.join("src/page.html")]
+        );
+
+        // Removed files are no longer matched
+        scanner.remove_files(vec![dir.join("index.html")]);
+        assert!(!scanner.matches(&dir.join("index.html")));
+    }
 }